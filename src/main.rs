@@ -1,25 +1,85 @@
+#![allow(clippy::upper_case_acronyms)]
+
+use aws_config::sts::AssumeRoleProvider;
 use aws_config::SdkConfig;
+use aws_sdk_cloudtrail as cloudtrail;
+#[cfg(feature = "cloudwatch-metrics")]
+use aws_sdk_cloudwatch as cloudwatch;
+use aws_sdk_ec2 as ec2;
+use aws_sdk_eventbridge as eventbridge;
+use aws_sdk_iam as iam;
 use aws_sdk_kms as kms;
+use aws_sdk_organizations as organizations;
 use aws_sdk_rds as rds;
-use chrono::{DateTime, NaiveDateTime, Utc};
-use clap::{Parser, ValueEnum};
-use inquire::{Confirm, InquireError, Select};
+use aws_sdk_servicequotas as servicequotas;
+use aws_sdk_sns as sns;
+use aws_sdk_sts as sts;
+use chrono::{DateTime, Timelike, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use cloudtrail::model::{Event, LookupAttribute, LookupAttributeKey};
+use iam::model::PolicyEvaluationDecisionType;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use inquire::{Confirm, InquireError, Select, Text};
 use kms::model::AliasListEntry;
+use kms::model::GrantListEntry;
 use kms::model::KeyListEntry;
-use std::collections::HashMap;
+use kms::model::KeyManagerType;
+use kms::model::KeyMetadata;
+use kms::model::OriginType;
+use organizations::model::AccountStatus;
+use rds::model::{
+    DbClusterSnapshot, DbSnapshot, Filter, OptionGroup, OrderableDbInstanceOption, SourceType, Tag,
+};
+use rds::types::SdkError;
+use rds::Region;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use sts::output::GetCallerIdentityOutput;
 use tokio::join;
-use tokio_stream::StreamExt;
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::sleep;
+use tokio_stream::{Stream, StreamExt};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(short, long)]
     db_identifier: Option<String>,
 
+    /// Look the cluster up by its immutable resource id instead of its identifier
+    #[arg(long, conflicts_with = "db_identifier")]
+    db_cluster_resource_id: Option<String>,
+
+    /// Look the instance up by its immutable resource id instead of its identifier
+    #[arg(long, conflicts_with_all = ["db_identifier", "db_cluster_resource_id"])]
+    dbi_resource_id: Option<String>,
+
     #[arg(short, long)]
     kms_key_id: Option<String>,
 
+    /// Only offer snapshots stored in the region or on an AWS Outpost
+    #[arg(value_enum, long)]
+    backup_target: Option<BackupTarget>,
+
+    /// Which keys to offer in the KMS key menu
+    #[arg(value_enum, long, default_value_t = KeyManager::Customer)]
+    key_manager: KeyManager,
+
     #[arg(value_enum, short = 't', long, default_value_t = DatabaseType::Database)]
     db_type: DatabaseType,
 
@@ -28,303 +88,6735 @@ struct Args {
 
     #[arg()]
     account_ids: Option<Vec<String>>,
-}
 
-#[derive(ValueEnum, Clone)]
-enum DatabaseType {
-    Cluster,
-    Database,
-}
+    #[arg(value_enum, short, long, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
 
-impl fmt::Display for DatabaseType {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            DatabaseType::Cluster => write!(f, "cluster"),
-            DatabaseType::Database => write!(f, "database"),
-        }
-    }
-}
+    /// Read newline-separated snapshot ARNs from stdin and share each of them
+    #[arg(long)]
+    snapshot_arns_from_stdin: bool,
 
-struct RDS {
-    client: rds::Client,
-}
+    /// Simulate the run, printing every API call that would be made as a JSON audit trail
+    #[arg(long, global = true)]
+    audit_mode: bool,
 
-impl RDS {
-    fn new(config: &SdkConfig) -> RDS {
-        RDS {
-            client: rds::Client::new(config),
-        }
-    }
+    /// Identifier of the copy made when the snapshot is not encrypted with the chosen KMS key
+    #[arg(long)]
+    new_snapshot_id: Option<String>,
 
-    async fn describe_instances(&self) -> Result<Vec<String>, rds::Error> {
-        let paginator = self
-            .client
-            .describe_db_instances()
-            .into_paginator()
-            .items()
-            .send();
+    /// Use snapshot identifiers as given instead of lowercasing them like the RDS API does
+    #[arg(long)]
+    no_force_snapshot_identifier_case: bool,
 
-        let instances = paginator.collect::<Result<Vec<_>, _>>().await?;
+    /// Point-in-time restore timestamp (RFC 3339) to include in the generated copy identifier
+    #[arg(long, value_name = "RFC3339")]
+    pitr_timestamp: Option<DateTime<Utc>>,
 
-        Ok(instances
-            .iter()
-            .filter(|db| db.db_cluster_identifier().is_none())
-            .map(|db| db.db_instance_identifier().unwrap().to_string())
-            .collect())
-    }
+    /// Print the copy's identifier and ARN as soon as it is initiated, skipping the share
+    #[arg(long)]
+    no_wait_for_copy: bool,
 
-    async fn describe_clusters(&self) -> Result<Vec<String>, rds::Error> {
-        let paginator = self
-            .client
-            .describe_db_clusters()
-            .into_paginator()
-            .items()
-            .send();
+    /// Share with at most this many accounts, taken in account id order
+    #[arg(long)]
+    max_share_per_run: Option<usize>,
 
-        let clusters = paginator.collect::<Result<Vec<_>, _>>().await?;
+    /// Skip showing engine, status, endpoint and tags after choosing a database from the menu
+    #[arg(long)]
+    no_detail_panel: bool,
 
-        Ok(clusters
-            .iter()
-            .map(|db| db.db_cluster_identifier().unwrap().to_string())
-            .collect())
-    }
+    /// Re-fetch the instance or cluster list every N seconds while the database menu is open
+    #[arg(long, value_name = "SECONDS")]
+    instance_list_refresh_interval: Option<u64>,
 
-    async fn describe_db_cluster_snapshots(
-        &self,
-        identifier: String,
-    ) -> Result<Vec<String>, rds::Error> {
-        let paginator = self
-            .client
-            .describe_db_cluster_snapshots()
-            .db_cluster_identifier(identifier)
-            .into_paginator()
-            .items()
-            .send();
+    /// Re-fetch the KMS key list every N seconds while the key menu is open
+    #[arg(long, value_name = "SECONDS")]
+    kms_key_refresh_interval: Option<u64>,
 
-        let snapshots = paginator.collect::<Result<Vec<_>, _>>().await?;
+    /// Re-fetch the snapshot list every N seconds while the snapshot menu is open
+    #[arg(long, value_name = "SECONDS")]
+    snapshot_list_refresh_interval: Option<u64>,
 
-        Ok(snapshots
-            .iter()
-            .map(|s| {
-                let snapshot_id = s.db_cluster_snapshot_identifier().unwrap();
-                let timestamp = s.snapshot_create_time().unwrap().secs();
+    /// Use a --kms-key-id ARN from another account without stopping
+    #[arg(long)]
+    allow_cross_account_kms: bool,
 
-                let naive = NaiveDateTime::from_timestamp_opt(timestamp, 0).unwrap();
-                let datetime: DateTime<Utc> = DateTime::from_utc(naive, Utc);
+    /// Publish CloudWatch metrics for the share once it completes
+    #[cfg(feature = "cloudwatch-metrics")]
+    #[arg(long)]
+    emit_metrics: bool,
 
-                format!("{}|{}", snapshot_id, datetime.format("%Y-%m-%d %H:%M:%S"))
-            })
-            .collect())
-    }
+    #[cfg(feature = "cloudwatch-metrics")]
+    #[arg(long, default_value = "RdsSnapshotShare")]
+    metrics_namespace: String,
 
-    async fn describe_db_snapshot_attributes(
-        &self,
-        snapshot_id: String,
-    ) -> Result<HashMap<String, Vec<String>>, rds::Error> {
-        let resp = self
-            .client
-            .describe_db_snapshot_attributes()
-            .db_snapshot_identifier(snapshot_id)
-            .send()
-            .await
-            .unwrap();
+    /// Publish a summary of the share to this SNS topic once it completes
+    #[arg(long, value_name = "ARN")]
+    sns_topic_arn: Option<String>,
 
-        let res = resp.db_snapshot_attributes_result().unwrap();
+    /// Also publish to --sns-topic-arn when a snapshot copy reaches this percentage
+    #[arg(long, value_name = "PERCENT", requires = "sns_topic_arn", value_parser = clap::value_parser!(i32).range(1..=100))]
+    snapshot_progress_notification_threshold: Option<i32>,
 
-        Ok(res
-            .db_snapshot_attributes()
-            .unwrap()
-            .iter()
-            .map(|attr| {
-                (
-                    attr.attribute_name().unwrap().to_string(),
-                    attr.attribute_values()
-                        .unwrap()
-                        .iter()
-                        .map(String::from)
-                        .collect(),
-                )
-            })
-            .collect())
-    }
-}
+    /// Put an event on EventBridge describing the share once it completes
+    #[arg(long)]
+    eventbridge_notification: bool,
 
-enum KeyType {
-    AWS,
-    Custom,
-}
+    #[arg(long, default_value = "default")]
+    eventbridge_bus_name: String,
 
-struct Key {
-    id: String,
-    alias: Option<String>,
-}
+    /// Post a summary of the share to this Slack incoming webhook once it completes
+    #[cfg(feature = "slack")]
+    #[arg(long, value_name = "URL")]
+    slack_webhook: Option<String>,
 
-struct KMS {
-    client: kms::Client,
+    /// Post a summary card to this Microsoft Teams incoming webhook once the share completes
+    #[cfg(feature = "teams")]
+    #[arg(long, value_name = "URL")]
+    teams_webhook: Option<String>,
+
+    /// Title of the Teams card, defaulting to the share status
+    #[cfg(feature = "teams")]
+    #[arg(long, value_name = "TEXT")]
+    teams_title: Option<String>,
+
+    /// Trigger a PagerDuty incident through the Events API v2 when the share fails
+    #[cfg(feature = "pagerduty")]
+    #[arg(long, value_name = "KEY")]
+    pagerduty_integration_key: Option<String>,
+
+    /// Resolve the PagerDuty incident for this snapshot or database when the share succeeds
+    #[cfg(feature = "pagerduty")]
+    #[arg(long, requires = "pagerduty_integration_key")]
+    pagerduty_resolve: bool,
+
+    /// Warn when the snapshot's lifecycle is managed by AWS Backup or Data Lifecycle Manager
+    #[arg(long)]
+    check_lifecycle_policy: bool,
+
+    /// Share snapshots already encrypted with a customer managed key as they are, and copy
+    /// unencrypted or AWS managed key snapshots onto --kms-key-id before sharing the copy
+    #[arg(long, requires = "kms_key_id")]
+    encrypt_and_copy_if_unencrypted: bool,
+
+    /// Add the current account to the accounts the snapshot is shared with
+    #[arg(long)]
+    share_with_self: bool,
+
+    /// Show the options in the snapshot's option group, which must be recreated in the target
+    /// account before restoring
+    #[arg(long)]
+    describe_option_groups: bool,
+
+    /// Write a restore runbook for the target account after sharing, as JSON when the path ends
+    /// in .json and Markdown otherwise
+    #[arg(long, value_name = "PATH")]
+    generate_restore_guide: Option<PathBuf>,
+
+    /// Show manual snapshot usage against the Service Quotas limit before copying
+    #[arg(long)]
+    check_service_quotas: bool,
+
+    /// Warn when --check-service-quotas finds usage above this percentage of the quota
+    #[arg(long, value_name = "PERCENT", default_value_t = 80, value_parser = clap::value_parser!(u8).range(1..=100))]
+    quota_usage_warning_threshold: u8,
+
+    /// Tag snapshot copies with the alias of the KMS key they are encrypted with
+    #[arg(long)]
+    snapshot_tag_kms_alias: bool,
+
+    /// Warn when automated backups are disabled or the preferred backup window is in progress
+    #[arg(long)]
+    check_automated_backup_settings: bool,
+
+    /// Ask before continuing when the database has a pending or in-progress engine upgrade
+    #[arg(long)]
+    check_db_upgrade_in_progress: bool,
+
+    /// Look up CloudTrail for modifications of the database in the last N minutes and
+    /// ask for confirmation if there are any
+    #[arg(long, value_name = "MINUTES")]
+    check_recently_modified: Option<i64>,
+
+    /// Show the RDS events of the selected database before snapshotting
+    #[arg(long)]
+    show_recent_events: bool,
+
+    /// How far back --show-recent-events looks
+    #[arg(long, value_name = "HOURS", default_value_t = 24)]
+    events_duration_hours: i32,
+
+    /// Print a Service Control Policy statement allowing the shared snapshot's sharing to be
+    /// modified, for inclusion in the organization's SCP document
+    #[arg(long)]
+    include_snapshot_in_org_policy: bool,
+
+    /// Longest snapshot identifier accepted for --snapshot-id; generated copy names are truncated
+    /// to fit
+    #[arg(long, value_name = "N", default_value_t = 255)]
+    max_identifier_length: usize,
+
+    /// Always prompt, even when there is only one database or snapshot to choose from
+    #[arg(long)]
+    no_auto_select: bool,
+
+    /// Allow a --snapshot-id that another account shared with this one as the source
+    #[arg(long)]
+    allow_shared_source: bool,
+
+    /// Don't tag snapshot copies with rds-snapshot-share:db-identifier
+    #[arg(long)]
+    no_db_identifier_tag: bool,
+
+    /// Check the number of snapshot copies already in progress before copying
+    #[arg(long)]
+    check_concurrent_snapshot_limit: bool,
+
+    /// Concurrent snapshot copies allowed in this account and region
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    max_concurrent_copies: usize,
+
+    /// With --check-concurrent-snapshot-limit, wait for a copy to finish instead of exiting
+    #[arg(long, requires = "check_concurrent_snapshot_limit")]
+    wait: bool,
+
+    /// Check each account exists by assuming --verify-role-name in it before sharing
+    #[arg(long)]
+    verify_accounts: bool,
+
+    #[arg(
+        long,
+        default_value = "OrganizationAccountAccessRole",
+        requires = "verify_accounts"
+    )]
+    verify_role_name: String,
+
+    /// Tag snapshot copies with rds-snapshot-share:created-by set to the caller's ARN
+    #[arg(long)]
+    snapshot_creation_source_tag: bool,
+
+    /// Prompt for the accounts to share with when none are given
+    #[arg(long)]
+    interactive_account_input: bool,
+
+    /// Log the RDS SDK version the tool was built with
+    #[arg(long)]
+    snapshot_management_api_version_check: bool,
+
+    /// Show the estimated monthly storage cost of the snapshot and of any copy
+    #[arg(long)]
+    sharing_cost_estimate: bool,
+
+    /// Snapshot storage price used by --sharing-cost-estimate, in USD per GiB-month
+    #[arg(long, value_name = "USD", default_value_t = 0.095)]
+    storage_price_per_gib: f64,
+
+    /// Add a statement to the --kms-key-id key policy allowing every account in the
+    /// organization to use the key, instead of relying on per-account access
+    #[arg(long, requires = "kms_key_id")]
+    kms_key_policy_auto_update_for_org: bool,
+
+    /// Show VPCs by their Name tag as well as their ID in the database details
+    #[arg(long)]
+    resolve_vpc_names: bool,
+
+    /// Export the shared snapshot to an S3 bucket in another account, e.g. s3://bucket/prefix
+    #[arg(long, value_name = "S3_URI", requires = "iam_role_arn")]
+    copy_to_account_s3: Option<String>,
+
+    /// IAM role RDS assumes to write the --copy-to-account-s3 export
+    #[arg(long, value_name = "ARN")]
+    iam_role_arn: Option<String>,
+
+    /// Identifier of the --copy-to-account-s3 export task, defaulting to a timestamped one
+    #[arg(long, value_name = "ID", requires = "copy_to_account_s3")]
+    export_task_id: Option<String>,
+
+    /// Warn when the KMS key's material is imported or backed by CloudHSM
+    #[arg(long)]
+    detect_cmk_encrypted_with_aws_managed_key: bool,
+
+    /// Tag the shared snapshot with the number of accounts it is shared with and when
+    #[arg(long)]
+    snapshot_tag_accounts_count: bool,
+
+    /// Drop target accounts the organization reports as suspended or closing
+    #[arg(value_enum, long)]
+    org_account_filter_status: Option<OrgAccountStatus>,
+
+    /// Show each key's description in the KMS key menu and in the summary
+    #[arg(long)]
+    key_description_display: bool,
+
+    /// Print the account, ARN and user ID of the caller before doing anything else
+    #[arg(long, global = true)]
+    display_caller_identity: bool,
+
+    /// Stop reading snapshots for the menu after the first N matching ones returned by the API.
+    ///
+    /// The API does not return snapshots newest first, so these are not necessarily the N
+    /// newest snapshots
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_snapshots: Option<u64>,
+
+    /// Storage type to restore the copy with. CopyDBSnapshot cannot change it, so it is
+    /// carried into --generate-restore-guide
+    #[arg(value_enum, long)]
+    copy_storage_type: Option<StorageType>,
+
+    /// RDS endpoint to use instead of the regional default, e.g. a VPC endpoint. Applies to the
+    /// configured region only
+    #[arg(long, value_name = "URL", global = true, value_parser = parse_https_url)]
+    rds_endpoint_url: Option<String>,
+
+    /// KMS endpoint to use instead of the regional default, e.g. a VPC endpoint. Applies to the
+    /// configured region only
+    #[arg(long, value_name = "URL", global = true, value_parser = parse_https_url)]
+    kms_endpoint_url: Option<String>,
+
+    /// Note when the database is an RDS Proxy target before sharing its snapshot
+    #[arg(long)]
+    check_db_proxy_targets: bool,
+
+    /// List the snapshots encrypted with --kms-key-id, exiting non-zero if there are any
+    #[arg(long, requires = "kms_key_id")]
+    check_kms_key_in_use: bool,
 }
 
-impl KMS {
-    fn new(config: &SdkConfig) -> KMS {
-        KMS {
-            client: kms::Client::new(config),
-        }
-    }
+#[derive(Subcommand)]
+enum Command {
+    /// Restore a snapshot within the current account
+    RestoreLocal(RestoreLocalArgs),
 
-    async fn list_aliases(&self) -> Result<Vec<AliasListEntry>, kms::Error> {
-        let paginator = self.client.list_aliases().into_paginator().items().send();
+    /// Poll a snapshot copy until it is available or has failed
+    CopyProgress(CopyProgressArgs),
 
-        Ok(paginator.collect::<Result<Vec<_>, _>>().await?)
-    }
+    /// Copy a snapshot shared from another account so this account owns it
+    ///
+    /// Cross-account sharing is a three step workflow:
+    ///
+    /// 1. In the source account, share the snapshot with the target account
+    ///    (`rds-snapshot-share -s <snapshot-id> <account-id>`).
+    ///
+    /// 2. Tell the target account which snapshot was shared, for example with
+    ///    --eventbridge-notification or one of the webhook notifications.
+    ///
+    /// 3. In the target account, run this subcommand with the shared snapshot's
+    ///    ARN. The copy is independent of the source account, which can then
+    ///    stop sharing or delete the original.
+    ///
+    /// Encrypted snapshots must be shared with a customer managed key that the
+    /// target account may use, and --kms-key-id must name a key in the target
+    /// account to encrypt the copy with.
+    CopyReceivedSnapshot(CopyReceivedSnapshotArgs),
 
-    async fn list_all_keys(&self) -> Result<Vec<KeyListEntry>, kms::Error> {
-        let paginator = self.client.list_keys().into_paginator().items().send();
+    /// Compare the accounts two snapshots are shared with
+    CompareAttributes(CompareAttributesArgs),
 
-        Ok(paginator.collect::<Result<Vec<_>, _>>().await?)
-    }
+    /// Share a snapshot with the same accounts as another snapshot
+    CopyAttributes(CopyAttributesArgs),
 
-    async fn list_keys(&self) -> Result<Vec<Key>, kms::Error> {
-        let aliases_future = self.list_aliases();
-        let keys_future = self.list_all_keys();
+    /// List every manual cluster and instance snapshot in the account
+    ListAllSnapshots(ListAllSnapshotsArgs),
 
-        let (aliases, keys) = join!(aliases_future, keys_future);
+    /// List the snapshots of one database without sharing anything
+    #[command(visible_alias = "snapshots")]
+    ListSnapshots(ListSnapshotsArgs),
 
-        let aliases = aliases.unwrap();
-        let alias_map: HashMap<_, _> = aliases
-            .iter()
-            .map(|al| {
-                let id = al.target_key_id().unwrap_or_default().to_string();
+    /// Show live progress for several snapshots until they are all available or failed
+    WatchAll(WatchAllArgs),
 
-                (id, al)
-            })
-            .collect();
+    /// Run the sharing jobs defined in a YAML manifest
+    BatchYaml(BatchYamlArgs),
 
-        let mut customer_managed_keys: Vec<Key> = vec![];
+    /// Count the manual and automated snapshots encrypted with each customer managed key
+    KmsKeyUsage,
 
-        for key in keys.unwrap() {
-            let id = key.key_id().unwrap();
+    /// List the grants on a KMS key
+    #[command(visible_alias = "list-kms-grants")]
+    ListGrants(ListGrantsArgs),
 
-            let (key_type, alias) = match alias_map.get(id) {
-                Some(&key_alias) => {
-                    let name = key_alias.alias_name().unwrap().to_string();
+    /// Check from the target account that a shared snapshot can be restored
+    SimulateRestore(SimulateRestoreArgs),
 
-                    if name.starts_with("alias/aws") {
-                        (KeyType::AWS, Some(name))
-                    } else {
-                        (KeyType::Custom, Some(name))
-                    }
-                }
-                None => (KeyType::Custom, None),
-            };
+    /// List RDS Proxies with their target groups and targets
+    DescribeProxies,
 
-            match key_type {
-                KeyType::Custom => customer_managed_keys.push(Key {
-                    id: id.to_string(),
-                    alias,
-                }),
-                _ => (),
-            }
-        }
+    /// Check that the RDS and KMS APIs are reachable with the current credentials
+    HealthCheck,
 
-        Ok(customer_managed_keys)
-    }
+    /// Show who changed a snapshot's sharing and when, from CloudTrail
+    SnapshotAttributeHistory(SnapshotAttributeHistoryArgs),
+
+    /// List every manual snapshot in every region enabled for the account
+    Inventory(ListAllSnapshotsArgs),
 }
 
-fn select(prompt: &str, choices: Vec<String>) -> Result<String, InquireError> {
-    let ans = Select::new(prompt, choices.clone()).prompt()?;
+#[derive(clap::Args)]
+struct SimulateRestoreArgs {
+    /// ARN of the shared snapshot
+    #[arg(long)]
+    snapshot_arn: String,
 
-    let index = choices.iter().position(|c| c.to_string() == ans).unwrap();
+    /// Role in the target account to assume for the checks
+    #[arg(long)]
+    target_role_arn: String,
 
-    Ok(choices[index].clone())
+    /// Instance class the target account intends to restore to
+    #[arg(long)]
+    instance_class: Option<String>,
 }
 
-fn select_rds(identifiers: Vec<String>) -> Result<String, InquireError> {
-    select("Please choose an RDS", identifiers)
+#[derive(clap::Args)]
+struct ListGrantsArgs {
+    #[arg(short, long)]
+    key_id: String,
+
+    /// Only show grants whose grantee principal contains this text
+    #[arg(long, value_name = "PATTERN")]
+    filter_grantee: Option<String>,
 }
 
-fn select_keys(keys: Vec<Key>) -> Result<String, InquireError> {
-    let keys: HashMap<_, _> = keys
-        .iter()
-        .map(|key| {
-            let id = &key.id;
+#[derive(clap::Args)]
+struct SnapshotAttributeHistoryArgs {
+    #[arg(short, long)]
+    snapshot_id: String,
 
-            match &key.alias {
-                Some(alias) => (alias.clone(), key),
-                None => (id.clone(), key),
-            }
-        })
-        .collect();
+    #[arg(value_enum, short = 't', long, default_value_t = DatabaseType::Database)]
+    db_type: DatabaseType,
+}
 
-    let ans = select(
-        &"Choose a KMS key to use for snapshot",
-        keys.keys().cloned().collect(),
-    )
-    .unwrap();
+#[derive(clap::Args)]
+struct BatchYamlArgs {
+    /// Manifest with a list of jobs under a top-level `jobs` key
+    #[arg(long, value_name = "PATH")]
+    file: PathBuf,
 
-    let key = keys.get(&ans);
+    /// Where to write the per-job results
+    #[arg(long, value_name = "PATH", default_value = "batch-results.yaml")]
+    results_file: PathBuf,
 
-    Ok(key.unwrap().id.clone())
+    /// How many jobs to run at once
+    #[arg(long, default_value_t = 4)]
+    parallelism: usize,
 }
 
-fn select_snapshot(snapshots: Vec<String>) -> Result<String, InquireError> {
-    select("Select a snapshot to copy", snapshots)
-}
+#[derive(clap::Args)]
+struct WatchAllArgs {
+    #[arg(long, value_delimiter = ',', required_unless_present = "accounts_file")]
+    snapshot_ids: Vec<String>,
+
+    /// File with one snapshot id per line
+    #[arg(long)]
+    accounts_file: Option<PathBuf>,
 
-fn confirm_use_exisitng_snapshot() -> Result<bool, InquireError> {
-    Confirm::new("Use an existing snapshot").prompt()
+    #[arg(value_enum, short = 't', long, default_value_t = DatabaseType::Database)]
+    db_type: DatabaseType,
 }
 
-#[tokio::main]
-async fn main() -> Result<(), rds::Error> {
-    let args = Args::parse();
+#[derive(clap::Args)]
+struct ListSnapshotsArgs {
+    #[arg(short, long)]
+    db_identifier: String,
 
-    let config = aws_config::load_from_env().await;
-    let rds = RDS::new(&config);
-    let kms = KMS::new(&config);
+    #[arg(value_enum, short = 't', long, default_value_t = DatabaseType::Database)]
+    db_type: DatabaseType,
 
-    let identifier = match args.db_identifier {
-        Some(id) => Ok(id),
-        None => {
-            let identifiers = match args.db_type {
-                DatabaseType::Database => rds.describe_instances().await.unwrap(),
-                DatabaseType::Cluster => rds.describe_clusters().await.unwrap(),
-            };
+    /// Only list snapshots of this type, such as manual or automated
+    #[arg(long)]
+    snapshot_type: Option<String>,
 
-            select_rds(identifiers)
-        }
-    }
-    .unwrap();
+    #[arg(value_enum, long)]
+    backup_target: Option<BackupTarget>,
 
-    let kms_key_id = match args.kms_key_id {
-        Some(kms_key_id) => Ok(kms_key_id),
-        None => {
-            let keys = kms.list_keys().await.unwrap();
+    #[arg(value_enum, short, long, default_value_t = ListFormat::Table)]
+    format: ListFormat,
 
-            select_keys(keys)
-        }
-    }
-    .unwrap();
+    /// Only print this page of the list, starting from 1
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    snapshot_list_page: Option<u64>,
 
-    let use_existing_snapshot = confirm_use_exisitng_snapshot();
+    #[arg(long, value_name = "M", default_value_t = 25, value_parser = clap::value_parser!(u64).range(1..))]
+    snapshot_list_page_size: u64,
 
-    let snapshot = match args.snapshot_id {
-        Some(snap) => snap,
-        None => {
-            let snapshots = rds
-                .describe_db_cluster_snapshots(identifier.clone())
-                .await
-                .unwrap();
+    /// Stop listing after the first N matching snapshots returned by the API.
+    ///
+    /// The API does not return snapshots newest first, so these are not necessarily the N
+    /// newest snapshots
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "snapshot_list_page")]
+    max_snapshots: Option<u64>,
+}
 
-            select_snapshot(snapshots).unwrap()
-        }
-    };
+#[derive(clap::Args)]
+struct ListAllSnapshotsArgs {
+    #[arg(value_enum, short, long, default_value_t = ListFormat::Table)]
+    format: ListFormat,
+}
 
-    println!(
-        "{} {} {} {}",
-        &identifier,
-        &kms_key_id,
-        use_existing_snapshot.unwrap(),
-        &snapshot,
-    );
+#[derive(clap::Args)]
+struct CopyAttributesArgs {
+    #[arg(long)]
+    source_snapshot_id: String,
 
-    Ok(())
+    #[arg(long)]
+    dest_snapshot_id: String,
+
+    #[arg(value_enum, short = 't', long, default_value_t = DatabaseType::Database)]
+    db_type: DatabaseType,
+
+    /// Add the source accounts without removing accounts only the destination is shared with
+    #[arg(long)]
+    merge: bool,
+
+    /// Tag the destination with the number of accounts it is shared with and when
+    #[arg(long)]
+    snapshot_tag_accounts_count: bool,
+}
+
+#[derive(clap::Args)]
+struct CompareAttributesArgs {
+    #[arg(long)]
+    snapshot_a: String,
+
+    #[arg(long)]
+    snapshot_b: String,
+
+    #[arg(value_enum, short = 't', long, default_value_t = DatabaseType::Database)]
+    db_type: DatabaseType,
+}
+
+#[derive(clap::Args)]
+struct CopyReceivedSnapshotArgs {
+    /// ARN of the snapshot shared with this account
+    #[arg(long)]
+    snapshot_arn: String,
+
+    #[arg(long)]
+    new_snapshot_id: String,
+
+    #[arg(short, long)]
+    kms_key_id: Option<String>,
+
+    /// Poll until the copy is available
+    #[arg(long)]
+    wait: bool,
+}
+
+#[derive(clap::Args)]
+struct CopyProgressArgs {
+    #[arg(short, long)]
+    snapshot_id: String,
+
+    #[arg(value_enum, short = 't', long, default_value_t = DatabaseType::Database)]
+    db_type: DatabaseType,
+
+    /// Write the latest progress line to this file on every poll
+    #[arg(long)]
+    progress_file: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct RestoreLocalArgs {
+    #[arg(long)]
+    source_snapshot_id: String,
+
+    #[arg(long)]
+    new_db_identifier: String,
+
+    #[arg(value_enum, short = 't', long, default_value_t = DatabaseType::Database)]
+    db_type: DatabaseType,
+
+    /// Instance class of the restored instance (or of the cluster's writer instance)
+    #[arg(long)]
+    instance_class: Option<String>,
+
+    #[arg(long)]
+    subnet_group: Option<String>,
+
+    #[arg(long, value_delimiter = ',')]
+    security_groups: Vec<String>,
+
+    /// Engine version of the restored cluster
+    #[arg(long)]
+    engine_version: Option<String>,
+
+    /// Warn when an Aurora MySQL snapshot is restored to a different major version
+    #[arg(long)]
+    detect_aurora_version: bool,
+
+    /// List the instance classes, storage types and Multi-AZ support the snapshot can be
+    /// restored to, exiting if --instance-class is not one of them
+    #[arg(long)]
+    describe_restore_targets: bool,
+
+    /// Poll until the restored database is available
+    #[arg(long)]
+    wait: bool,
+}
+
+#[derive(ValueEnum, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DatabaseType {
+    Cluster,
+    Database,
+}
+
+impl fmt::Display for DatabaseType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatabaseType::Cluster => write!(f, "cluster"),
+            DatabaseType::Database => write!(f, "database"),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone)]
+enum StorageType {
+    Gp2,
+    Gp3,
+    Io1,
+}
+
+impl fmt::Display for StorageType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageType::Gp2 => write!(f, "gp2"),
+            StorageType::Gp3 => write!(f, "gp3"),
+            StorageType::Io1 => write!(f, "io1"),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone)]
+enum OrgAccountStatus {
+    /// Only organization accounts in the ACTIVE state
+    Active,
+    /// Every account, including suspended and closing ones
+    All,
+}
+
+#[derive(ValueEnum, Clone)]
+enum BackupTarget {
+    Region,
+    Outpost,
+}
+
+impl BackupTarget {
+    fn api_value(&self) -> &'static str {
+        match self {
+            BackupTarget::Region => "region",
+            BackupTarget::Outpost => "outposts",
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct SnapshotFilter {
+    backup_target: Option<BackupTarget>,
+    max_snapshots: Option<usize>,
+}
+
+impl SnapshotFilter {
+    fn is_full(&self, count: usize) -> bool {
+        self.max_snapshots.is_some_and(|max| count >= max)
+    }
+}
+
+struct SnapshotInfo {
+    id: String,
+    arn: String,
+    status: String,
+    percent_progress: i32,
+    kms_key_id: Option<String>,
+    availability_zones: Vec<String>,
+    snapshot_type: String,
+    snapshot_target: String,
+    tags: Vec<String>,
+    engine: String,
+    engine_version: String,
+    option_group_name: Option<String>,
+    allocated_storage_gib: i32,
+    storage_type: Option<String>,
+}
+
+impl From<&DbClusterSnapshot> for SnapshotInfo {
+    fn from(s: &DbClusterSnapshot) -> Self {
+        SnapshotInfo {
+            id: s.db_cluster_snapshot_identifier().unwrap().to_string(),
+            arn: s.db_cluster_snapshot_arn().unwrap_or_default().to_string(),
+            status: s.status().unwrap_or_default().to_string(),
+            percent_progress: s.percent_progress(),
+            kms_key_id: s.kms_key_id().map(String::from),
+            availability_zones: s.availability_zones().unwrap_or_default().to_vec(),
+            snapshot_type: s.snapshot_type().unwrap_or_default().to_string(),
+            snapshot_target: "region".to_string(),
+            tags: format_tags(s.tag_list()),
+            engine: s.engine().unwrap_or_default().to_string(),
+            engine_version: s.engine_version().unwrap_or_default().to_string(),
+            option_group_name: None,
+            storage_type: None,
+            allocated_storage_gib: s.allocated_storage(),
+        }
+    }
+}
+
+impl From<&DbSnapshot> for SnapshotInfo {
+    fn from(s: &DbSnapshot) -> Self {
+        SnapshotInfo {
+            id: s.db_snapshot_identifier().unwrap().to_string(),
+            arn: s.db_snapshot_arn().unwrap_or_default().to_string(),
+            status: s.status().unwrap_or_default().to_string(),
+            percent_progress: s.percent_progress(),
+            kms_key_id: s.kms_key_id().map(String::from),
+            availability_zones: s
+                .availability_zone()
+                .map(String::from)
+                .into_iter()
+                .collect(),
+            snapshot_type: s.snapshot_type().unwrap_or_default().to_string(),
+            snapshot_target: s.snapshot_target().unwrap_or("region").to_string(),
+            tags: format_tags(s.tag_list()),
+            engine: s.engine().unwrap_or_default().to_string(),
+            engine_version: s.engine_version().unwrap_or_default().to_string(),
+            option_group_name: s.option_group_name().map(String::from),
+            storage_type: s.storage_type().map(String::from),
+            allocated_storage_gib: s.allocated_storage(),
+        }
+    }
+}
+
+impl Command {
+    /// Snapshot identifiers given on the command line, checked and normalized before any API
+    /// call.
+    fn snapshot_ids_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Command::RestoreLocal(args) => vec![&mut args.source_snapshot_id],
+            Command::CopyProgress(args) => vec![&mut args.snapshot_id],
+            Command::CopyReceivedSnapshot(args) => vec![&mut args.new_snapshot_id],
+            Command::CompareAttributes(args) => vec![&mut args.snapshot_a, &mut args.snapshot_b],
+            Command::CopyAttributes(args) => {
+                vec![&mut args.source_snapshot_id, &mut args.dest_snapshot_id]
+            }
+            Command::WatchAll(args) => args.snapshot_ids.iter_mut().collect(),
+            Command::SnapshotAttributeHistory(args) => vec![&mut args.snapshot_id],
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BatchManifest {
+    jobs: Vec<BatchJob>,
+}
+
+#[derive(Deserialize, Clone)]
+struct BatchJob {
+    db_identifier: String,
+    db_type: Option<DatabaseType>,
+    /// Only consider snapshots whose identifier contains this text
+    snapshot_filter: Option<String>,
+    kms_key_id: String,
+    target_accounts: Vec<String>,
+    /// Regions the job runs in, defaulting to the configured region
+    #[serde(default)]
+    target_regions: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct BatchJobResult {
+    db_identifier: String,
+    region: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    shared_with: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SnapshotRecord {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<String>,
+    db_type: DatabaseType,
+    db_identifier: String,
+    snapshot_id: String,
+    created: String,
+    allocated_storage_gib: i32,
+    encrypted: bool,
+    kms_key_id: Option<String>,
+    snapshot_target: String,
+    shared_with: Vec<String>,
+    #[serde(skip)]
+    status: String,
+}
+
+impl SnapshotRecord {
+    fn sharing(&self) -> String {
+        match self.shared_with.len() {
+            0 => "private".to_string(),
+            _ if self.shared_with.iter().any(|id| id == "all") => "public".to_string(),
+            n => format!("{} accounts", n),
+        }
+    }
+}
+
+/// Snapshots still being created may not have a create time yet; those show an empty one.
+fn format_create_time(time: Option<&rds::types::DateTime>) -> String {
+    time.and_then(|time| DateTime::from_timestamp(time.secs(), 0))
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+impl From<&DbClusterSnapshot> for SnapshotRecord {
+    fn from(snapshot: &DbClusterSnapshot) -> Self {
+        SnapshotRecord {
+            region: None,
+            db_type: DatabaseType::Cluster,
+            db_identifier: snapshot
+                .db_cluster_identifier()
+                .unwrap_or_default()
+                .to_string(),
+            snapshot_id: snapshot
+                .db_cluster_snapshot_identifier()
+                .unwrap()
+                .to_string(),
+            created: format_create_time(snapshot.snapshot_create_time()),
+            allocated_storage_gib: snapshot.allocated_storage(),
+            kms_key_id: snapshot.kms_key_id().map(String::from),
+            snapshot_target: "region".to_string(),
+            encrypted: snapshot.storage_encrypted(),
+            shared_with: vec![],
+            status: snapshot.status().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+impl From<&DbSnapshot> for SnapshotRecord {
+    fn from(snapshot: &DbSnapshot) -> Self {
+        SnapshotRecord {
+            region: None,
+            db_type: DatabaseType::Database,
+            db_identifier: snapshot
+                .db_instance_identifier()
+                .unwrap_or_default()
+                .to_string(),
+            snapshot_id: snapshot.db_snapshot_identifier().unwrap().to_string(),
+            created: format_create_time(snapshot.snapshot_create_time()),
+            allocated_storage_gib: snapshot.allocated_storage(),
+            kms_key_id: snapshot.kms_key_id().map(String::from),
+            snapshot_target: snapshot.snapshot_target().unwrap_or("region").to_string(),
+            encrypted: snapshot.encrypted(),
+            shared_with: vec![],
+            status: snapshot.status().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// Why a share stopped before completing; main reports it and exits non-zero.
+#[derive(Debug)]
+struct ShareError(String);
+
+impl fmt::Display for ShareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<E: std::error::Error> From<E> for ShareError {
+    fn from(err: E) -> Self {
+        ShareError(err.to_string())
+    }
+}
+
+#[derive(Serialize)]
+struct ShareSummary {
+    db_identifier: String,
+    source_snapshot_id: String,
+    snapshot_id: String,
+    kms_key_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kms_key_description: Option<String>,
+    account_ids: Vec<String>,
+    copied: bool,
+    status: String,
+    duration_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    org_policy_statement: Option<Value>,
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+impl ShareSummary {
+    fn print(&self, format: &OutputFormat) {
+        match format {
+            OutputFormat::Text => (),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(self).unwrap()),
+            OutputFormat::Env => {
+                let vars = [
+                    ("RDS_SHARE_DB_IDENTIFIER", self.db_identifier.clone()),
+                    (
+                        "RDS_SHARE_SOURCE_SNAPSHOT_ID",
+                        self.source_snapshot_id.clone(),
+                    ),
+                    ("RDS_SHARE_SNAPSHOT_ID", self.snapshot_id.clone()),
+                    ("RDS_SHARE_KMS_KEY_ID", self.kms_key_id.clone()),
+                    ("RDS_SHARE_ACCOUNT_IDS", self.account_ids.join(",")),
+                    ("RDS_SHARE_STATUS", self.status.clone()),
+                ];
+
+                for (name, value) in vars {
+                    println!("export {}={}", name, shell_quote(&value));
+                }
+
+                if let Some(statement) = &self.org_policy_statement {
+                    println!(
+                        "export RDS_SHARE_ORG_POLICY_STATEMENT={}",
+                        shell_quote(&statement.to_string())
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AuditEntry {
+    operation: String,
+    parameters: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    would_succeed: bool,
+}
+
+#[derive(Default)]
+struct AuditTrail {
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditTrail {
+    fn describe(&mut self, operation: &str, parameters: Value, result: Value) {
+        self.entries.push(AuditEntry {
+            operation: operation.to_string(),
+            parameters,
+            result: Some(result),
+            would_succeed: true,
+        });
+    }
+
+    fn mutate(&mut self, operation: &str, parameters: Value) {
+        self.entries.push(AuditEntry {
+            operation: operation.to_string(),
+            parameters,
+            result: None,
+            would_succeed: true,
+        });
+    }
+
+    fn share_snapshot(
+        &mut self,
+        db_type: &DatabaseType,
+        snapshot_id: &str,
+        account_ids: &[String],
+    ) {
+        match db_type {
+            DatabaseType::Cluster => self.mutate(
+                "ModifyDBClusterSnapshotAttribute",
+                json!({
+                    "DBClusterSnapshotIdentifier": snapshot_id,
+                    "AttributeName": "restore",
+                    "ValuesToAdd": account_ids,
+                }),
+            ),
+            DatabaseType::Database => self.mutate(
+                "ModifyDBSnapshotAttribute",
+                json!({
+                    "DBSnapshotIdentifier": snapshot_id,
+                    "AttributeName": "restore",
+                    "ValuesToAdd": account_ids,
+                }),
+            ),
+        }
+    }
+
+    fn tag_shared_accounts(&mut self, snapshot_id: &str) {
+        self.mutate(
+            "AddTagsToResource",
+            json!({
+                "ResourceName": snapshot_id,
+                "TagKeys": [
+                    "rds-snapshot-share:accounts-shared",
+                    "rds-snapshot-share:last-shared-at",
+                ],
+            }),
+        );
+    }
+
+    fn modify_restore_accounts(
+        &mut self,
+        db_type: &DatabaseType,
+        snapshot_id: &str,
+        account_ids: &[String],
+        remove_account_ids: &[String],
+    ) {
+        match db_type {
+            DatabaseType::Cluster => self.mutate(
+                "ModifyDBClusterSnapshotAttribute",
+                json!({
+                    "DBClusterSnapshotIdentifier": snapshot_id,
+                    "AttributeName": "restore",
+                    "ValuesToAdd": account_ids,
+                    "ValuesToRemove": remove_account_ids,
+                }),
+            ),
+            DatabaseType::Database => self.mutate(
+                "ModifyDBSnapshotAttribute",
+                json!({
+                    "DBSnapshotIdentifier": snapshot_id,
+                    "AttributeName": "restore",
+                    "ValuesToAdd": account_ids,
+                    "ValuesToRemove": remove_account_ids,
+                }),
+            ),
+        }
+    }
+
+    fn copy_snapshot(
+        &mut self,
+        db_type: &DatabaseType,
+        source_snapshot_id: &str,
+        target_snapshot_id: &str,
+        kms_key_id: Option<&str>,
+    ) {
+        match db_type {
+            DatabaseType::Cluster => self.mutate(
+                "CopyDBClusterSnapshot",
+                json!({
+                    "SourceDBClusterSnapshotIdentifier": source_snapshot_id,
+                    "TargetDBClusterSnapshotIdentifier": target_snapshot_id,
+                    "KmsKeyId": kms_key_id,
+                }),
+            ),
+            DatabaseType::Database => self.mutate(
+                "CopyDBSnapshot",
+                json!({
+                    "SourceDBSnapshotIdentifier": source_snapshot_id,
+                    "TargetDBSnapshotIdentifier": target_snapshot_id,
+                    "KmsKeyId": kms_key_id,
+                }),
+            ),
+        }
+    }
+
+    fn print(&self) {
+        println!("{}", serde_json::to_string_pretty(&self.entries).unwrap());
+    }
+}
+
+#[derive(ValueEnum, Clone)]
+enum ListFormat {
+    Table,
+    /// One JSON object per line
+    Json,
+    Csv,
+}
+
+#[derive(ValueEnum, Clone)]
+enum OutputFormat {
+    Text,
+    Json,
+    Env,
+}
+
+struct SnapshotArn {
+    region: String,
+    db_type: DatabaseType,
+    snapshot_id: String,
+}
+
+impl FromStr for SnapshotArn {
+    type Err = String;
+
+    fn from_str(arn: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = arn.splitn(7, ':').collect();
+
+        match parts[..] {
+            ["arn", _, "rds", region, _, resource_type, snapshot_id]
+                if !region.is_empty() && !snapshot_id.is_empty() =>
+            {
+                let db_type = match resource_type {
+                    "cluster-snapshot" => DatabaseType::Cluster,
+                    "snapshot" => DatabaseType::Database,
+                    _ => return Err(format!("{} is not an RDS snapshot ARN", arn)),
+                };
+
+                Ok(SnapshotArn {
+                    region: region.to_string(),
+                    db_type,
+                    snapshot_id: snapshot_id.to_string(),
+                })
+            }
+            _ => Err(format!("{} is not a valid ARN", arn)),
+        }
+    }
+}
+
+struct DatabaseDetails {
+    engine: String,
+    engine_version: String,
+    status: String,
+    endpoint: String,
+    tags: Vec<String>,
+    port: i32,
+    instance_class: Option<String>,
+    parameter_group: Option<String>,
+    option_group: Option<String>,
+    subnet_group: Option<String>,
+    vpc_id: Option<String>,
+    vpc_name: Option<String>,
+    security_groups: Vec<String>,
+    backup_retention_period: i32,
+    preferred_backup_window: Option<String>,
+    pending_engine_version: Option<String>,
+}
+
+impl fmt::Display for DatabaseDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Engine:   {} {}", self.engine, self.engine_version)?;
+        writeln!(f, "Status:   {}", self.status)?;
+        writeln!(f, "Endpoint: {}", self.endpoint)?;
+
+        if let Some(vpc_id) = &self.vpc_id {
+            match &self.vpc_name {
+                Some(name) => writeln!(f, "VPC:      {} ({})", vpc_id, name)?,
+                None => writeln!(f, "VPC:      {}", vpc_id)?,
+            }
+        }
+
+        if self.engine.starts_with("oracle-") && self.engine.ends_with("-cdb") {
+            writeln!(
+                f,
+                "Topology: multi-tenant container database; snapshots include every pluggable database"
+            )?;
+        }
+
+        write!(f, "Tags:     {}", self.tags.join(", "))
+    }
+}
+
+struct EventInfo {
+    date: String,
+    message: String,
+    categories: Vec<String>,
+}
+
+struct ProxyInfo {
+    name: String,
+    endpoint: String,
+    engine_family: String,
+    status: String,
+    target_groups: Vec<String>,
+    targets: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RestoreGuide {
+    db_type: DatabaseType,
+    snapshot_arn: String,
+    kms_key_arn: String,
+    account_ids: Vec<String>,
+    engine: String,
+    engine_version: String,
+    port: i32,
+    parameter_group: Option<String>,
+    option_group: Option<String>,
+    subnet_group: Option<String>,
+    security_groups: Vec<String>,
+    kms_key_actions: Vec<String>,
+    iam_actions: Vec<String>,
+    steps: Vec<String>,
+}
+
+impl RestoreGuide {
+    fn new(
+        db_type: &DatabaseType,
+        snapshot_arn: String,
+        kms_key_arn: String,
+        account_ids: Vec<String>,
+        details: DatabaseDetails,
+        storage_type: Option<&StorageType>,
+    ) -> Self {
+        let kms_key_actions = ["kms:Decrypt", "kms:DescribeKey", "kms:CreateGrant"]
+            .map(String::from)
+            .to_vec();
+
+        let subnet_group = details
+            .subnet_group
+            .clone()
+            .unwrap_or_else(|| "<subnet-group>".to_string());
+        let instance_class = details
+            .instance_class
+            .clone()
+            .unwrap_or_else(|| "<instance-class>".to_string());
+        let mut restore_options = format!(
+            "--db-subnet-group-name {} --vpc-security-group-ids <security-group-ids>",
+            subnet_group
+        );
+
+        let (iam_actions, steps) = match db_type {
+            DatabaseType::Cluster => {
+                if let Some(group) = &details.parameter_group {
+                    restore_options += &format!(" --db-cluster-parameter-group-name {}", group);
+                }
+
+                (
+                    ["rds:RestoreDBClusterFromSnapshot", "rds:CreateDBInstance"],
+                    vec![
+                        format!(
+                            "aws rds restore-db-cluster-from-snapshot --db-cluster-identifier <cluster-id> --snapshot-identifier {} --engine {} --engine-version {} --kms-key-id <target-kms-key-id> {}",
+                            snapshot_arn, details.engine, details.engine_version, restore_options
+                        ),
+                        format!(
+                            "aws rds create-db-instance --db-instance-identifier <instance-id> --db-cluster-identifier <cluster-id> --engine {} --db-instance-class {}",
+                            details.engine, instance_class
+                        ),
+                        "aws rds wait db-instance-available --db-instance-identifier <instance-id>"
+                            .to_string(),
+                    ],
+                )
+            }
+            DatabaseType::Database => {
+                if let Some(group) = &details.parameter_group {
+                    restore_options += &format!(" --db-parameter-group-name {}", group);
+                }
+                if let Some(group) = &details.option_group {
+                    restore_options += &format!(" --option-group-name {}", group);
+                }
+                if let Some(storage_type) = storage_type {
+                    restore_options += &format!(" --storage-type {}", storage_type);
+                }
+
+                (
+                    ["rds:RestoreDBInstanceFromDBSnapshot", "rds:AddTagsToResource"],
+                    vec![
+                        format!(
+                            "aws rds copy-db-snapshot --source-db-snapshot-identifier {} --target-db-snapshot-identifier <snapshot-id> --kms-key-id <target-kms-key-id>",
+                            snapshot_arn
+                        ),
+                        format!(
+                            "aws rds restore-db-instance-from-db-snapshot --db-instance-identifier <instance-id> --db-snapshot-identifier <snapshot-id> --db-instance-class {} {}",
+                            instance_class, restore_options
+                        ),
+                        "aws rds wait db-instance-available --db-instance-identifier <instance-id>"
+                            .to_string(),
+                    ],
+                )
+            }
+        };
+
+        RestoreGuide {
+            db_type: db_type.clone(),
+            snapshot_arn,
+            kms_key_arn,
+            account_ids,
+            engine: details.engine,
+            engine_version: details.engine_version,
+            port: details.port,
+            parameter_group: details.parameter_group,
+            option_group: details.option_group,
+            subnet_group: details.subnet_group,
+            security_groups: details.security_groups,
+            kms_key_actions,
+            iam_actions: iam_actions.map(String::from).to_vec(),
+            steps,
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let none = "none".to_string();
+        let mut guide = vec![
+            "# Restore guide".to_string(),
+            String::new(),
+            format!("Snapshot: `{}`", self.snapshot_arn),
+            format!("Shared with: {}", self.account_ids.join(", ")),
+            format!("Engine: {} {}", self.engine, self.engine_version),
+            String::new(),
+            "## Prerequisites".to_string(),
+            String::new(),
+            "Groups and security groups belong to the source account; create equivalents in the target account.".to_string(),
+            String::new(),
+            format!(
+                "- Parameter group: {}",
+                self.parameter_group.as_ref().unwrap_or(&none)
+            ),
+            format!(
+                "- Option group: {}",
+                self.option_group.as_ref().unwrap_or(&none)
+            ),
+            format!(
+                "- Subnet group: {}",
+                self.subnet_group.as_ref().unwrap_or(&none)
+            ),
+            format!(
+                "- Security groups: {} (allow inbound traffic on port {})",
+                self.security_groups.join(", "),
+                self.port
+            ),
+            String::new(),
+            "## KMS key access".to_string(),
+            String::new(),
+            format!(
+                "The key policy of `{}` must allow the target account {}.",
+                self.kms_key_arn,
+                self.kms_key_actions.join(", ")
+            ),
+            String::new(),
+            "## IAM permissions".to_string(),
+            String::new(),
+            format!(
+                "The restoring principal needs {} and {} on the key.",
+                self.iam_actions.join(", "),
+                self.kms_key_actions.join(", ")
+            ),
+            String::new(),
+            "## Steps".to_string(),
+            String::new(),
+        ];
+
+        for (i, step) in self.steps.iter().enumerate() {
+            guide.push(format!("{}. `{}`", i + 1, step));
+        }
+
+        guide.join("\n") + "\n"
+    }
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let contents = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::to_string_pretty(self).unwrap(),
+            _ => self.to_markdown(),
+        };
+
+        fs::write(path, contents)
+    }
+}
+
+fn format_tags(tags: Option<&[Tag]>) -> Vec<String> {
+    tags.unwrap_or_default()
+        .iter()
+        .map(|tag| {
+            format!(
+                "{}={}",
+                tag.key().unwrap_or_default(),
+                tag.value().unwrap_or_default()
+            )
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+struct RDS {
+    client: rds::Client,
+}
+
+impl RDS {
+    fn with_endpoint(config: &SdkConfig, endpoint_url: Option<&str>) -> RDS {
+        let config = match endpoint_url {
+            Some(url) => rds::config::Builder::from(config).endpoint_url(url).build(),
+            None => rds::Config::new(config),
+        };
+
+        RDS {
+            client: rds::Client::from_conf(config),
+        }
+    }
+
+    async fn describe_instances(&self, filters: Vec<Filter>) -> Result<Vec<String>, rds::Error> {
+        let paginator = self
+            .client
+            .describe_db_instances()
+            .set_filters((!filters.is_empty()).then_some(filters))
+            .into_paginator()
+            .items()
+            .send();
+
+        let instances = paginator.collect::<Result<Vec<_>, _>>().await?;
+
+        Ok(instances
+            .iter()
+            .filter(|db| db.db_cluster_identifier().is_none())
+            .map(|db| db.db_instance_identifier().unwrap().to_string())
+            .collect())
+    }
+
+    async fn describe_clusters(&self, filters: Vec<Filter>) -> Result<Vec<String>, rds::Error> {
+        let paginator = self
+            .client
+            .describe_db_clusters()
+            .set_filters((!filters.is_empty()).then_some(filters))
+            .into_paginator()
+            .items()
+            .send();
+
+        let clusters = paginator.collect::<Result<Vec<_>, _>>().await?;
+
+        Ok(clusters
+            .iter()
+            .map(|db| db.db_cluster_identifier().unwrap().to_string())
+            .collect())
+    }
+
+    async fn describe_databases(&self, db_type: &DatabaseType) -> Result<Vec<String>, rds::Error> {
+        match db_type {
+            DatabaseType::Cluster => self.describe_clusters(vec![]).await,
+            DatabaseType::Database => self.describe_instances(vec![]).await,
+        }
+    }
+
+    async fn describe_subnet_group_vpc(
+        &self,
+        subnet_group: String,
+    ) -> Result<Option<String>, rds::Error> {
+        let resp = self
+            .client
+            .describe_db_subnet_groups()
+            .db_subnet_group_name(subnet_group)
+            .send()
+            .await?;
+
+        Ok(resp
+            .db_subnet_groups()
+            .unwrap_or_default()
+            .first()
+            .and_then(|group| group.vpc_id())
+            .map(String::from))
+    }
+
+    async fn describe_database_details(
+        &self,
+        db_type: &DatabaseType,
+        identifier: String,
+    ) -> Result<DatabaseDetails, rds::Error> {
+        match db_type {
+            DatabaseType::Cluster => {
+                let resp = self
+                    .client
+                    .describe_db_clusters()
+                    .db_cluster_identifier(identifier)
+                    .send()
+                    .await?;
+                let db = &resp.db_clusters().unwrap()[0];
+
+                Ok(DatabaseDetails {
+                    engine: db.engine().unwrap_or_default().to_string(),
+                    engine_version: db.engine_version().unwrap_or_default().to_string(),
+                    status: db.status().unwrap_or_default().to_string(),
+                    endpoint: format!(
+                        "{}:{}",
+                        db.endpoint().unwrap_or_default(),
+                        db.port().unwrap_or_default()
+                    ),
+                    tags: format_tags(db.tag_list()),
+                    port: db.port().unwrap_or_default(),
+                    instance_class: db.db_cluster_instance_class().map(String::from),
+                    parameter_group: db.db_cluster_parameter_group().map(String::from),
+                    option_group: None,
+                    subnet_group: db.db_subnet_group().map(String::from),
+                    vpc_id: None,
+                    vpc_name: None,
+                    backup_retention_period: db.backup_retention_period().unwrap_or_default(),
+                    preferred_backup_window: db.preferred_backup_window().map(String::from),
+                    pending_engine_version: db
+                        .pending_modified_values()
+                        .and_then(|values| values.engine_version())
+                        .map(String::from),
+                    security_groups: db
+                        .vpc_security_groups()
+                        .unwrap_or_default()
+                        .iter()
+                        .filter_map(|sg| sg.vpc_security_group_id().map(String::from))
+                        .collect(),
+                })
+            }
+            DatabaseType::Database => {
+                let resp = self
+                    .client
+                    .describe_db_instances()
+                    .db_instance_identifier(identifier)
+                    .send()
+                    .await?;
+                let db = &resp.db_instances().unwrap()[0];
+                let endpoint = db.endpoint();
+
+                Ok(DatabaseDetails {
+                    engine: db.engine().unwrap_or_default().to_string(),
+                    engine_version: db.engine_version().unwrap_or_default().to_string(),
+                    status: db.db_instance_status().unwrap_or_default().to_string(),
+                    endpoint: format!(
+                        "{}:{}",
+                        endpoint.and_then(|e| e.address()).unwrap_or_default(),
+                        endpoint.map(|e| e.port()).unwrap_or_default()
+                    ),
+                    tags: format_tags(db.tag_list()),
+                    port: endpoint.map(|e| e.port()).unwrap_or_default(),
+                    instance_class: db.db_instance_class().map(String::from),
+                    parameter_group: db
+                        .db_parameter_groups()
+                        .unwrap_or_default()
+                        .first()
+                        .and_then(|group| group.db_parameter_group_name())
+                        .map(String::from),
+                    option_group: db
+                        .option_group_memberships()
+                        .unwrap_or_default()
+                        .first()
+                        .and_then(|group| group.option_group_name())
+                        .map(String::from),
+                    subnet_group: db
+                        .db_subnet_group()
+                        .and_then(|group| group.db_subnet_group_name())
+                        .map(String::from),
+                    vpc_id: db
+                        .db_subnet_group()
+                        .and_then(|group| group.vpc_id())
+                        .map(String::from),
+                    vpc_name: None,
+                    backup_retention_period: db.backup_retention_period(),
+                    preferred_backup_window: db.preferred_backup_window().map(String::from),
+                    pending_engine_version: db
+                        .pending_modified_values()
+                        .and_then(|values| values.engine_version())
+                        .map(String::from),
+                    security_groups: db
+                        .vpc_security_groups()
+                        .unwrap_or_default()
+                        .iter()
+                        .filter_map(|sg| sg.vpc_security_group_id().map(String::from))
+                        .collect(),
+                })
+            }
+        }
+    }
+
+    async fn describe_db_cluster_snapshots(
+        &self,
+        identifier: String,
+        filter: &SnapshotFilter,
+    ) -> Result<Vec<String>, rds::Error> {
+        if let Some(BackupTarget::Outpost) = filter.backup_target {
+            return Ok(vec![]);
+        }
+
+        let mut paginator = self
+            .client
+            .describe_db_cluster_snapshots()
+            .db_cluster_identifier(identifier)
+            .into_paginator()
+            .items()
+            .send();
+
+        let mut snapshots = vec![];
+
+        while !filter.is_full(snapshots.len()) {
+            match paginator.next().await {
+                Some(snapshot) => snapshots.push(snapshot?),
+                None => break,
+            }
+        }
+
+        snapshots.sort_by_key(|s| Reverse(s.snapshot_create_time().map(|t| t.secs())));
+
+        Ok(snapshots
+            .iter()
+            .map(|s| {
+                let snapshot_id = s.db_cluster_snapshot_identifier().unwrap();
+
+                format!(
+                    "{}|{}",
+                    snapshot_id,
+                    format_create_time(s.snapshot_create_time())
+                )
+            })
+            .collect())
+    }
+
+    async fn describe_db_snapshots(
+        &self,
+        identifier: String,
+        filter: &SnapshotFilter,
+    ) -> Result<Vec<String>, rds::Error> {
+        let mut paginator = self
+            .client
+            .describe_db_snapshots()
+            .db_instance_identifier(identifier)
+            .into_paginator()
+            .items()
+            .send();
+
+        let mut snapshots = vec![];
+
+        while !filter.is_full(snapshots.len()) {
+            let snapshot = match paginator.next().await {
+                Some(snapshot) => snapshot?,
+                None => break,
+            };
+
+            let target = snapshot.snapshot_target().unwrap_or("region");
+
+            if filter
+                .backup_target
+                .as_ref()
+                .is_none_or(|backup_target| backup_target.api_value() == target)
+            {
+                snapshots.push(snapshot);
+            }
+        }
+
+        snapshots.sort_by_key(|s| Reverse(s.snapshot_create_time().map(|t| t.secs())));
+
+        Ok(snapshots
+            .iter()
+            .map(|s| {
+                let snapshot_id = s.db_snapshot_identifier().unwrap();
+
+                format!(
+                    "{}|{}",
+                    snapshot_id,
+                    format_create_time(s.snapshot_create_time())
+                )
+            })
+            .collect())
+    }
+
+    async fn describe_snapshots(
+        &self,
+        db_type: &DatabaseType,
+        identifier: String,
+        filter: &SnapshotFilter,
+    ) -> Result<Vec<String>, rds::Error> {
+        match db_type {
+            DatabaseType::Cluster => self.describe_db_cluster_snapshots(identifier, filter).await,
+            DatabaseType::Database => self.describe_db_snapshots(identifier, filter).await,
+        }
+    }
+
+    async fn describe_db_snapshot_attributes(
+        &self,
+        snapshot_id: String,
+    ) -> Result<HashMap<String, Vec<String>>, rds::Error> {
+        let resp = self
+            .client
+            .describe_db_snapshot_attributes()
+            .db_snapshot_identifier(snapshot_id)
+            .send()
+            .await?;
+
+        let res = resp.db_snapshot_attributes_result().unwrap();
+
+        Ok(res
+            .db_snapshot_attributes()
+            .unwrap_or_default()
+            .iter()
+            .map(|attr| {
+                (
+                    attr.attribute_name().unwrap().to_string(),
+                    attr.attribute_values()
+                        .unwrap_or_default()
+                        .iter()
+                        .map(String::from)
+                        .collect(),
+                )
+            })
+            .collect())
+    }
+
+    async fn describe_db_cluster_snapshot_attributes(
+        &self,
+        snapshot_id: String,
+    ) -> Result<HashMap<String, Vec<String>>, rds::Error> {
+        let resp = self
+            .client
+            .describe_db_cluster_snapshot_attributes()
+            .db_cluster_snapshot_identifier(snapshot_id)
+            .send()
+            .await?;
+
+        let res = resp.db_cluster_snapshot_attributes_result().unwrap();
+
+        Ok(res
+            .db_cluster_snapshot_attributes()
+            .unwrap_or_default()
+            .iter()
+            .map(|attr| {
+                (
+                    attr.attribute_name().unwrap().to_string(),
+                    attr.attribute_values()
+                        .unwrap_or_default()
+                        .iter()
+                        .map(String::from)
+                        .collect(),
+                )
+            })
+            .collect())
+    }
+
+    async fn describe_restore_accounts(
+        &self,
+        db_type: &DatabaseType,
+        snapshot_id: String,
+    ) -> Result<Vec<String>, rds::Error> {
+        let mut attributes = match db_type {
+            DatabaseType::Cluster => {
+                self.describe_db_cluster_snapshot_attributes(snapshot_id)
+                    .await?
+            }
+            DatabaseType::Database => self.describe_db_snapshot_attributes(snapshot_id).await?,
+        };
+
+        Ok(attributes.remove("restore").unwrap_or_default())
+    }
+
+    fn stream_db_cluster_snapshots(
+        &self,
+        identifier: Option<String>,
+        snapshot_type: Option<String>,
+    ) -> impl Stream<Item = Result<SnapshotRecord, rds::Error>> {
+        self.client
+            .describe_db_cluster_snapshots()
+            .set_db_cluster_identifier(identifier)
+            .set_snapshot_type(snapshot_type)
+            .into_paginator()
+            .items()
+            .send()
+            .map(|snapshot| Ok(SnapshotRecord::from(&snapshot?)))
+    }
+
+    fn stream_db_snapshots(
+        &self,
+        identifier: Option<String>,
+        snapshot_type: Option<String>,
+    ) -> impl Stream<Item = Result<SnapshotRecord, rds::Error>> {
+        self.client
+            .describe_db_snapshots()
+            .set_db_instance_identifier(identifier)
+            .set_snapshot_type(snapshot_type)
+            .into_paginator()
+            .items()
+            .send()
+            .map(|snapshot| Ok(SnapshotRecord::from(&snapshot?)))
+    }
+
+    async fn describe_events(
+        &self,
+        source_id: String,
+        source_type: String,
+        hours: i32,
+    ) -> Result<Vec<EventInfo>, rds::Error> {
+        let paginator = self
+            .client
+            .describe_events()
+            .source_identifier(source_id)
+            .source_type(SourceType::from(source_type.as_str()))
+            .duration(hours * 60)
+            .into_paginator()
+            .items()
+            .send();
+
+        let events = paginator.collect::<Result<Vec<_>, _>>().await?;
+
+        Ok(events
+            .iter()
+            .map(|event| EventInfo {
+                date: event
+                    .date()
+                    .and_then(|date| DateTime::from_timestamp(date.secs(), 0))
+                    .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default(),
+                message: event.message().unwrap_or_default().to_string(),
+                categories: event
+                    .event_categories()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(String::from)
+                    .collect(),
+            })
+            .collect())
+    }
+
+    async fn start_export_task(
+        &self,
+        export_task_id: String,
+        source_arn: String,
+        bucket: String,
+        prefix: Option<String>,
+        iam_role_arn: String,
+        kms_key_id: String,
+    ) -> Result<String, rds::Error> {
+        let resp = self
+            .client
+            .start_export_task()
+            .export_task_identifier(export_task_id)
+            .source_arn(source_arn)
+            .s3_bucket_name(bucket)
+            .set_s3_prefix(prefix)
+            .iam_role_arn(iam_role_arn)
+            .kms_key_id(kms_key_id)
+            .send()
+            .await?;
+
+        Ok(resp.status().unwrap_or_default().to_string())
+    }
+
+    async fn count_in_progress_copies(&self, db_type: &DatabaseType) -> Result<usize, rds::Error> {
+        let in_progress = |status: Option<&str>| {
+            matches!(status, Some("creating") | Some("copying") | Some("pending"))
+        };
+
+        Ok(match db_type {
+            DatabaseType::Cluster => self
+                .client
+                .describe_db_cluster_snapshots()
+                .snapshot_type("manual")
+                .into_paginator()
+                .items()
+                .send()
+                .collect::<Result<Vec<_>, _>>()
+                .await?
+                .iter()
+                .filter(|s| in_progress(s.status()))
+                .count(),
+            DatabaseType::Database => self
+                .client
+                .describe_db_snapshots()
+                .snapshot_type("manual")
+                .into_paginator()
+                .items()
+                .send()
+                .collect::<Result<Vec<_>, _>>()
+                .await?
+                .iter()
+                .filter(|s| in_progress(s.status()))
+                .count(),
+        })
+    }
+
+    async fn ping(&self) -> Result<(), rds::Error> {
+        self.client
+            .describe_db_engine_versions()
+            .max_records(20)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn describe_option_groups(
+        &self,
+        engine: String,
+        option_group_name: Option<String>,
+    ) -> Result<Vec<OptionGroup>, rds::Error> {
+        let resp = self
+            .client
+            .describe_option_groups()
+            .engine_name(engine)
+            .set_option_group_name(option_group_name)
+            .send()
+            .await?;
+
+        Ok(resp.option_groups_list().unwrap_or_default().to_vec())
+    }
+
+    async fn describe_shared_snapshot(
+        &self,
+        db_type: &DatabaseType,
+        snapshot_arn: String,
+    ) -> Result<Option<SnapshotInfo>, rds::Error> {
+        Ok(match db_type {
+            DatabaseType::Cluster => self
+                .client
+                .describe_db_cluster_snapshots()
+                .db_cluster_snapshot_identifier(snapshot_arn)
+                .snapshot_type("shared")
+                .include_shared(true)
+                .send()
+                .await?
+                .db_cluster_snapshots()
+                .unwrap_or_default()
+                .first()
+                .map(SnapshotInfo::from),
+            DatabaseType::Database => self
+                .client
+                .describe_db_snapshots()
+                .db_snapshot_identifier(snapshot_arn)
+                .snapshot_type("shared")
+                .include_shared(true)
+                .send()
+                .await?
+                .db_snapshots()
+                .unwrap_or_default()
+                .first()
+                .map(SnapshotInfo::from),
+        })
+    }
+
+    async fn describe_orderable_db_instance_options(
+        &self,
+        engine: String,
+        engine_version: String,
+    ) -> Result<Vec<OrderableDbInstanceOption>, rds::Error> {
+        let paginator = self
+            .client
+            .describe_orderable_db_instance_options()
+            .engine(engine)
+            .engine_version(engine_version)
+            .into_paginator()
+            .items()
+            .send();
+
+        Ok(paginator.collect::<Result<Vec<_>, _>>().await?)
+    }
+
+    async fn describe_orderable_instance_classes(
+        &self,
+        engine: String,
+        engine_version: String,
+        instance_class: Option<String>,
+    ) -> Result<Vec<String>, rds::Error> {
+        let options = self
+            .describe_orderable_db_instance_options(engine, engine_version)
+            .await?;
+
+        Ok(options
+            .iter()
+            .filter_map(|option| option.db_instance_class())
+            .filter(|class| {
+                instance_class
+                    .as_deref()
+                    .is_none_or(|wanted| *class == wanted)
+            })
+            .map(String::from)
+            .collect())
+    }
+
+    async fn describe_account_quota_usage(&self) -> Result<HashMap<String, i64>, rds::Error> {
+        let resp = self.client.describe_account_attributes().send().await?;
+
+        Ok(resp
+            .account_quotas()
+            .unwrap_or_default()
+            .iter()
+            .map(|quota| {
+                (
+                    quota.account_quota_name().unwrap_or_default().to_string(),
+                    quota.used(),
+                )
+            })
+            .collect())
+    }
+
+    async fn describe_db_proxies(&self) -> Result<Vec<ProxyInfo>, rds::Error> {
+        let db_proxies = self
+            .client
+            .describe_db_proxies()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+        let mut proxies = vec![];
+
+        for proxy in db_proxies {
+            let name = proxy.db_proxy_name().unwrap().to_string();
+
+            let (target_groups, targets) = join!(
+                self.client
+                    .describe_db_proxy_target_groups()
+                    .db_proxy_name(&name)
+                    .into_paginator()
+                    .items()
+                    .send()
+                    .collect::<Result<Vec<_>, _>>(),
+                self.client
+                    .describe_db_proxy_targets()
+                    .db_proxy_name(&name)
+                    .into_paginator()
+                    .items()
+                    .send()
+                    .collect::<Result<Vec<_>, _>>(),
+            );
+
+            proxies.push(ProxyInfo {
+                endpoint: proxy.endpoint().unwrap_or_default().to_string(),
+                engine_family: proxy.engine_family().unwrap_or_default().to_string(),
+                status: proxy
+                    .status()
+                    .map(|status| status.as_str().to_string())
+                    .unwrap_or_default(),
+                target_groups: target_groups?
+                    .iter()
+                    .filter_map(|group| group.target_group_name().map(String::from))
+                    .collect(),
+                targets: targets?
+                    .iter()
+                    .filter_map(|target| {
+                        target
+                            .tracked_cluster_id()
+                            .or(target.rds_resource_id())
+                            .map(String::from)
+                    })
+                    .collect(),
+                name,
+            });
+        }
+
+        Ok(proxies)
+    }
+
+    async fn describe_snapshots_by_kms_key(
+        &self,
+        key_arn: &str,
+    ) -> Result<Vec<String>, rds::Error> {
+        let cluster_snapshots = self
+            .client
+            .describe_db_cluster_snapshots()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+
+        let snapshots = self
+            .client
+            .describe_db_snapshots()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+
+        let cluster_snapshot_ids = cluster_snapshots
+            .iter()
+            .filter(|s| s.kms_key_id() == Some(key_arn))
+            .map(|s| s.db_cluster_snapshot_identifier().unwrap().to_string());
+
+        let snapshot_ids = snapshots
+            .iter()
+            .filter(|s| s.kms_key_id() == Some(key_arn))
+            .map(|s| s.db_snapshot_identifier().unwrap().to_string());
+
+        Ok(cluster_snapshot_ids.chain(snapshot_ids).collect())
+    }
+
+    async fn describe_db_cluster_snapshot(
+        &self,
+        snapshot_id: String,
+    ) -> Result<DbClusterSnapshot, rds::Error> {
+        let resp = self
+            .client
+            .describe_db_cluster_snapshots()
+            .db_cluster_snapshot_identifier(snapshot_id)
+            .send()
+            .await?;
+
+        Ok(resp.db_cluster_snapshots().unwrap()[0].clone())
+    }
+
+    async fn describe_db_snapshot(&self, snapshot_id: String) -> Result<DbSnapshot, rds::Error> {
+        let resp = self
+            .client
+            .describe_db_snapshots()
+            .db_snapshot_identifier(snapshot_id)
+            .send()
+            .await?;
+
+        Ok(resp.db_snapshots().unwrap()[0].clone())
+    }
+
+    async fn describe_snapshot(
+        &self,
+        db_type: &DatabaseType,
+        snapshot_id: String,
+    ) -> Result<SnapshotInfo, rds::Error> {
+        Ok(match db_type {
+            DatabaseType::Cluster => {
+                (&self.describe_db_cluster_snapshot(snapshot_id).await?).into()
+            }
+            DatabaseType::Database => (&self.describe_db_snapshot(snapshot_id).await?).into(),
+        })
+    }
+
+    async fn copy_db_cluster_snapshot(
+        &self,
+        source_snapshot_id: String,
+        target_snapshot_id: String,
+        kms_key_id: Option<String>,
+        tags: Vec<Tag>,
+    ) -> Result<SnapshotInfo, rds::Error> {
+        let resp = self
+            .client
+            .copy_db_cluster_snapshot()
+            .source_db_cluster_snapshot_identifier(source_snapshot_id)
+            .target_db_cluster_snapshot_identifier(target_snapshot_id)
+            .set_kms_key_id(kms_key_id)
+            .set_tags((!tags.is_empty()).then_some(tags))
+            .send()
+            .await?;
+
+        Ok(resp.db_cluster_snapshot().unwrap().into())
+    }
+
+    async fn copy_db_snapshot(
+        &self,
+        source_snapshot_id: String,
+        target_snapshot_id: String,
+        kms_key_id: Option<String>,
+        tags: Vec<Tag>,
+    ) -> Result<SnapshotInfo, rds::Error> {
+        let resp = self
+            .client
+            .copy_db_snapshot()
+            .source_db_snapshot_identifier(source_snapshot_id)
+            .target_db_snapshot_identifier(target_snapshot_id)
+            .set_kms_key_id(kms_key_id)
+            .set_tags((!tags.is_empty()).then_some(tags))
+            .send()
+            .await?;
+
+        Ok(resp.db_snapshot().unwrap().into())
+    }
+
+    async fn copy_snapshot(
+        &self,
+        db_type: &DatabaseType,
+        source_snapshot_id: String,
+        target_snapshot_id: String,
+        kms_key_id: Option<String>,
+        tags: Vec<Tag>,
+    ) -> Result<SnapshotInfo, rds::Error> {
+        match db_type {
+            DatabaseType::Cluster => {
+                self.copy_db_cluster_snapshot(
+                    source_snapshot_id,
+                    target_snapshot_id,
+                    kms_key_id,
+                    tags,
+                )
+                .await
+            }
+            DatabaseType::Database => {
+                self.copy_db_snapshot(source_snapshot_id, target_snapshot_id, kms_key_id, tags)
+                    .await
+            }
+        }
+    }
+
+    async fn find_snapshot(
+        &self,
+        db_type: &DatabaseType,
+        snapshot_id: String,
+    ) -> Result<Option<SnapshotInfo>, rds::Error> {
+        match db_type {
+            DatabaseType::Cluster => match self
+                .client
+                .describe_db_cluster_snapshots()
+                .db_cluster_snapshot_identifier(snapshot_id)
+                .send()
+                .await
+            {
+                Ok(resp) => Ok(resp
+                    .db_cluster_snapshots()
+                    .unwrap_or_default()
+                    .first()
+                    .map(SnapshotInfo::from)),
+                Err(SdkError::ServiceError(err))
+                    if err.err().is_db_cluster_snapshot_not_found_fault() =>
+                {
+                    Ok(None)
+                }
+                Err(err) => Err(err.into()),
+            },
+            DatabaseType::Database => match self
+                .client
+                .describe_db_snapshots()
+                .db_snapshot_identifier(snapshot_id)
+                .send()
+                .await
+            {
+                Ok(resp) => Ok(resp
+                    .db_snapshots()
+                    .unwrap_or_default()
+                    .first()
+                    .map(SnapshotInfo::from)),
+                Err(SdkError::ServiceError(err)) if err.err().is_db_snapshot_not_found_fault() => {
+                    Ok(None)
+                }
+                Err(err) => Err(err.into()),
+            },
+        }
+    }
+
+    async fn describe_subnet_group_availability_zones(
+        &self,
+        subnet_group: String,
+    ) -> Result<Vec<String>, rds::Error> {
+        let resp = self
+            .client
+            .describe_db_subnet_groups()
+            .db_subnet_group_name(subnet_group)
+            .send()
+            .await?;
+
+        Ok(resp
+            .db_subnet_groups()
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|group| group.subnets().unwrap_or_default())
+            .filter_map(|subnet| subnet.subnet_availability_zone()?.name())
+            .map(String::from)
+            .collect())
+    }
+
+    async fn restore_db_cluster_from_snapshot(
+        &self,
+        snapshot_id: String,
+        identifier: String,
+        engine: String,
+        engine_version: Option<String>,
+        subnet_group: Option<String>,
+        security_groups: Vec<String>,
+    ) -> Result<(), rds::Error> {
+        self.client
+            .restore_db_cluster_from_snapshot()
+            .snapshot_identifier(snapshot_id)
+            .db_cluster_identifier(identifier)
+            .engine(engine)
+            .set_engine_version(engine_version)
+            .set_db_subnet_group_name(subnet_group)
+            .set_vpc_security_group_ids((!security_groups.is_empty()).then_some(security_groups))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn create_cluster_instance(
+        &self,
+        cluster_identifier: String,
+        identifier: String,
+        engine: String,
+        instance_class: String,
+    ) -> Result<(), rds::Error> {
+        self.client
+            .create_db_instance()
+            .db_cluster_identifier(cluster_identifier)
+            .db_instance_identifier(identifier)
+            .engine(engine)
+            .db_instance_class(instance_class)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn restore_db_instance_from_db_snapshot(
+        &self,
+        snapshot_id: String,
+        identifier: String,
+        instance_class: Option<String>,
+        subnet_group: Option<String>,
+        security_groups: Vec<String>,
+    ) -> Result<(), rds::Error> {
+        self.client
+            .restore_db_instance_from_db_snapshot()
+            .db_snapshot_identifier(snapshot_id)
+            .db_instance_identifier(identifier)
+            .set_db_instance_class(instance_class)
+            .set_db_subnet_group_name(subnet_group)
+            .set_vpc_security_group_ids((!security_groups.is_empty()).then_some(security_groups))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn describe_instance_status(&self, identifier: String) -> Result<String, rds::Error> {
+        let resp = self
+            .client
+            .describe_db_instances()
+            .db_instance_identifier(identifier)
+            .send()
+            .await?;
+
+        Ok(resp
+            .db_instances()
+            .unwrap_or_default()
+            .first()
+            .and_then(|db| db.db_instance_status())
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    async fn describe_cluster_status(&self, identifier: String) -> Result<String, rds::Error> {
+        let resp = self
+            .client
+            .describe_db_clusters()
+            .db_cluster_identifier(identifier)
+            .send()
+            .await?;
+
+        Ok(resp
+            .db_clusters()
+            .unwrap_or_default()
+            .first()
+            .and_then(|db| db.status())
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    async fn share_db_cluster_snapshot(
+        &self,
+        snapshot_id: String,
+        account_ids: Vec<String>,
+        remove_account_ids: Vec<String>,
+    ) -> Result<Vec<String>, ShareError> {
+        let resp = self
+            .client
+            .modify_db_cluster_snapshot_attribute()
+            .db_cluster_snapshot_identifier(&snapshot_id)
+            .attribute_name("restore")
+            .set_values_to_add((!account_ids.is_empty()).then_some(account_ids))
+            .set_values_to_remove((!remove_account_ids.is_empty()).then_some(remove_account_ids))
+            .send()
+            .await;
+
+        if let Err(SdkError::ServiceError(err)) = &resp {
+            if err.err().code() == Some("AccessDenied") {
+                return Err(access_denied(
+                    "rds:ModifyDBClusterSnapshotAttribute",
+                    &snapshot_id,
+                ));
+            }
+        }
+
+        let resp = resp?;
+
+        Ok(resp
+            .db_cluster_snapshot_attributes_result()
+            .and_then(|res| res.db_cluster_snapshot_attributes())
+            .unwrap_or_default()
+            .iter()
+            .filter(|attr| attr.attribute_name() == Some("restore"))
+            .flat_map(|attr| attr.attribute_values().unwrap_or_default())
+            .map(String::from)
+            .collect())
+    }
+
+    async fn share_db_snapshot(
+        &self,
+        snapshot_id: String,
+        account_ids: Vec<String>,
+        remove_account_ids: Vec<String>,
+    ) -> Result<Vec<String>, ShareError> {
+        let resp = self
+            .client
+            .modify_db_snapshot_attribute()
+            .db_snapshot_identifier(&snapshot_id)
+            .attribute_name("restore")
+            .set_values_to_add((!account_ids.is_empty()).then_some(account_ids))
+            .set_values_to_remove((!remove_account_ids.is_empty()).then_some(remove_account_ids))
+            .send()
+            .await;
+
+        if let Err(SdkError::ServiceError(err)) = &resp {
+            if err.err().code() == Some("AccessDenied") {
+                return Err(access_denied("rds:ModifyDBSnapshotAttribute", &snapshot_id));
+            }
+        }
+
+        let resp = resp?;
+
+        Ok(resp
+            .db_snapshot_attributes_result()
+            .and_then(|res| res.db_snapshot_attributes())
+            .unwrap_or_default()
+            .iter()
+            .filter(|attr| attr.attribute_name() == Some("restore"))
+            .flat_map(|attr| attr.attribute_values().unwrap_or_default())
+            .map(String::from)
+            .collect())
+    }
+
+    async fn share_snapshot(
+        &self,
+        db_type: &DatabaseType,
+        snapshot_id: String,
+        account_ids: Vec<String>,
+    ) -> Result<Vec<String>, ShareError> {
+        self.modify_restore_accounts(db_type, snapshot_id, account_ids, vec![])
+            .await
+    }
+
+    async fn tag_shared_accounts(
+        &self,
+        db_type: &DatabaseType,
+        snapshot_id: String,
+        accounts_shared: usize,
+    ) -> Result<(), rds::Error> {
+        let snapshot = self.describe_snapshot(db_type, snapshot_id).await?;
+
+        self.client
+            .add_tags_to_resource()
+            .resource_name(snapshot.arn)
+            .tags(
+                Tag::builder()
+                    .key("rds-snapshot-share:accounts-shared")
+                    .value(accounts_shared.to_string())
+                    .build(),
+            )
+            .tags(
+                Tag::builder()
+                    .key("rds-snapshot-share:last-shared-at")
+                    .value(Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                    .build(),
+            )
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn modify_restore_accounts(
+        &self,
+        db_type: &DatabaseType,
+        snapshot_id: String,
+        account_ids: Vec<String>,
+        remove_account_ids: Vec<String>,
+    ) -> Result<Vec<String>, ShareError> {
+        match db_type {
+            DatabaseType::Cluster => {
+                self.share_db_cluster_snapshot(snapshot_id, account_ids, remove_account_ids)
+                    .await
+            }
+            DatabaseType::Database => {
+                self.share_db_snapshot(snapshot_id, account_ids, remove_account_ids)
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone)]
+enum KeyManager {
+    /// Only customer managed keys, which can be shared with other accounts
+    Customer,
+    /// Customer managed keys and AWS managed keys such as alias/aws/rds.
+    ///
+    /// AWS managed key policies cannot be changed to let other accounts use the key, so a
+    /// snapshot encrypted with one cannot be restored elsewhere. Listing them is useful for
+    /// inspection, but sharing refuses an AWS managed key; copy the snapshot onto a customer
+    /// managed key first.
+    Aws,
+}
+
+enum KeyType {
+    AWS,
+    Custom,
+}
+
+struct Key {
+    id: String,
+    alias: Option<String>,
+    description: Option<String>,
+}
+
+impl Key {
+    fn label(&self) -> String {
+        let name = self.alias.clone().unwrap_or_else(|| self.id.clone());
+
+        match self.description.as_deref().filter(|d| !d.is_empty()) {
+            Some(description) if description.chars().count() > 60 => {
+                let truncated: String = description.chars().take(57).collect();
+
+                format!("{} | {}...", name, truncated)
+            }
+            Some(description) => format!("{} | {}", name, description),
+            None => name,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct KMS {
+    client: kms::Client,
+}
+
+impl KMS {
+    fn with_endpoint(config: &SdkConfig, endpoint_url: Option<&str>) -> KMS {
+        let config = match endpoint_url {
+            Some(url) => kms::config::Builder::from(config).endpoint_url(url).build(),
+            None => kms::Config::new(config),
+        };
+
+        KMS {
+            client: kms::Client::from_conf(config),
+        }
+    }
+
+    async fn list_aliases(&self) -> Result<Vec<AliasListEntry>, kms::Error> {
+        let paginator = self.client.list_aliases().into_paginator().items().send();
+
+        Ok(paginator.collect::<Result<Vec<_>, _>>().await?)
+    }
+
+    async fn ping(&self) -> Result<(), kms::Error> {
+        self.client.list_aliases().limit(1).send().await?;
+
+        Ok(())
+    }
+
+    async fn find_alias(&self, key_id: &str) -> Result<Option<String>, kms::Error> {
+        let aliases = self.list_aliases().await?;
+
+        Ok(aliases
+            .iter()
+            .find(|alias| alias.target_key_id() == Some(key_id))
+            .and_then(|alias| alias.alias_name())
+            .map(String::from))
+    }
+
+    async fn allow_organization(&self, key_id: &str, org_id: &str) -> Result<bool, kms::Error> {
+        let resp = self
+            .client
+            .get_key_policy()
+            .key_id(key_id)
+            .policy_name("default")
+            .send()
+            .await?;
+
+        let mut policy: Value = serde_json::from_str(resp.policy().unwrap()).unwrap();
+
+        let statements = match policy["Statement"].take() {
+            Value::Array(statements) => statements,
+            Value::Null => vec![],
+            statement => vec![statement],
+        };
+
+        if statements
+            .iter()
+            .any(|statement| statement["Sid"] == ORG_KEY_POLICY_SID)
+        {
+            return Ok(false);
+        }
+
+        policy["Statement"] = Value::Array(
+            statements
+                .into_iter()
+                .chain([org_key_policy_statement(org_id)])
+                .collect(),
+        );
+
+        self.client
+            .put_key_policy()
+            .key_id(key_id)
+            .policy_name("default")
+            .policy(policy.to_string())
+            .send()
+            .await?;
+
+        Ok(true)
+    }
+
+    async fn list_grants(&self, key_id: &str) -> Result<Vec<GrantListEntry>, kms::Error> {
+        let paginator = self
+            .client
+            .list_grants()
+            .key_id(key_id)
+            .into_paginator()
+            .items()
+            .send();
+
+        Ok(paginator.collect::<Result<Vec<_>, _>>().await?)
+    }
+
+    async fn list_all_keys(&self) -> Result<Vec<KeyListEntry>, kms::Error> {
+        let paginator = self.client.list_keys().into_paginator().items().send();
+
+        Ok(paginator.collect::<Result<Vec<_>, _>>().await?)
+    }
+
+    async fn describe_key(&self, key_id: String) -> Result<KeyMetadata, kms::Error> {
+        let resp = self.client.describe_key().key_id(key_id).send().await?;
+
+        Ok(resp.key_metadata().unwrap().clone())
+    }
+
+    async fn describe_keys(&self, keys: Vec<Key>) -> Vec<Key> {
+        let mut descriptions = JoinSet::new();
+
+        for key in keys {
+            let kms = self.clone();
+
+            descriptions.spawn(async move {
+                let description = kms
+                    .describe_key(key.id.clone())
+                    .await
+                    .ok()
+                    .and_then(|metadata| metadata.description().map(String::from));
+
+                Key { description, ..key }
+            });
+        }
+
+        let mut keys = vec![];
+
+        while let Some(key) = descriptions.join_next().await {
+            keys.push(key.unwrap());
+        }
+
+        keys
+    }
+
+    async fn list_keys(&self, key_manager: &KeyManager) -> Result<Vec<Key>, kms::Error> {
+        let aliases_future = self.list_aliases();
+        let keys_future = self.list_all_keys();
+
+        let (aliases, keys) = join!(aliases_future, keys_future);
+
+        let aliases = aliases?;
+        let alias_map: HashMap<_, _> = aliases
+            .iter()
+            .map(|al| {
+                let id = al.target_key_id().unwrap_or_default().to_string();
+
+                (id, al)
+            })
+            .collect();
+
+        let mut listed_keys: Vec<Key> = vec![];
+
+        for key in keys? {
+            let id = match key.key_id() {
+                Some(id) => id,
+                None => continue,
+            };
+
+            let (key_type, alias) = match alias_map.get(id) {
+                Some(&key_alias) => {
+                    let name = key_alias.alias_name().unwrap_or_default().to_string();
+
+                    if name.starts_with("alias/aws") {
+                        (KeyType::AWS, Some(name))
+                    } else {
+                        (KeyType::Custom, Some(name))
+                    }
+                }
+                None => (KeyType::Custom, None),
+            };
+
+            match (key_type, key_manager) {
+                (KeyType::Custom, _) | (KeyType::AWS, KeyManager::Aws) => listed_keys.push(Key {
+                    id: id.to_string(),
+                    alias,
+                    description: None,
+                }),
+                (KeyType::AWS, KeyManager::Customer) => (),
+            }
+        }
+
+        Ok(listed_keys)
+    }
+}
+
+struct STS {
+    client: sts::Client,
+}
+
+impl STS {
+    fn new(config: &SdkConfig) -> STS {
+        STS {
+            client: sts::Client::new(config),
+        }
+    }
+
+    async fn get_caller_identity(&self) -> Result<GetCallerIdentityOutput, sts::Error> {
+        Ok(self.client.get_caller_identity().send().await?)
+    }
+
+    async fn verify_assume_role(&self, role_arn: String) -> Result<(), String> {
+        match self
+            .client
+            .assume_role()
+            .role_arn(role_arn)
+            .role_session_name("rds-snapshot-share-verify")
+            .send()
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(SdkError::ServiceError(err)) => Err(err
+                .err()
+                .code()
+                .map(|code| format!("{}: {}", code, err.err().message().unwrap_or_default()))
+                .unwrap_or_else(|| err.err().to_string())),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+const REFRESH_CHOICE: &str = "[refresh list]";
+
+fn check_sdk_version() {
+    eprintln!("Using aws-sdk-rds {}", rds::PKG_VERSION);
+}
+
+fn refresh_in_background<T, F, Fut>(interval: u64, initial: T, fetch: F) -> watch::Receiver<T>
+where
+    T: Send + Sync + 'static,
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Option<T>> + Send,
+{
+    let (tx, rx) = watch::channel(initial);
+
+    tokio::spawn(async move {
+        loop {
+            sleep(Duration::from_secs(interval)).await;
+
+            if let Some(latest) = fetch().await {
+                if tx.send(latest).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+fn select_refreshing(
+    prompt: &str,
+    choices: &mut watch::Receiver<Vec<String>>,
+) -> Result<String, InquireError> {
+    loop {
+        let mut options = vec![REFRESH_CHOICE.to_string()];
+        options.extend(choices.borrow_and_update().iter().cloned());
+
+        let ans = select(prompt, options)?;
+
+        if ans != REFRESH_CHOICE {
+            return Ok(ans);
+        }
+    }
+}
+
+#[cfg(feature = "cloudwatch-metrics")]
+struct CloudWatch {
+    client: cloudwatch::Client,
+}
+
+#[cfg(feature = "cloudwatch-metrics")]
+impl CloudWatch {
+    fn new(config: &SdkConfig) -> CloudWatch {
+        CloudWatch {
+            client: cloudwatch::Client::new(config),
+        }
+    }
+
+    async fn put_share_metrics(
+        &self,
+        namespace: &str,
+        summary: &ShareSummary,
+    ) -> Result<(), cloudwatch::Error> {
+        use cloudwatch::model::{MetricDatum, StandardUnit};
+
+        let datum = |name: &str, value: f64, unit: StandardUnit| {
+            MetricDatum::builder()
+                .metric_name(name)
+                .value(value)
+                .unit(unit)
+                .build()
+        };
+
+        self.client
+            .put_metric_data()
+            .namespace(namespace)
+            .metric_data(datum("SnapshotShareSuccess", 1.0, StandardUnit::Count))
+            .metric_data(datum(
+                "SnapshotShareDuration",
+                summary.duration_ms as f64,
+                StandardUnit::Milliseconds,
+            ))
+            .metric_data(datum(
+                "SnapshotCopyInitiated",
+                u8::from(summary.copied) as f64,
+                StandardUnit::Count,
+            ))
+            .metric_data(datum(
+                "AccountsSharedWith",
+                summary.account_ids.len() as f64,
+                StandardUnit::Count,
+            ))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+struct IAM {
+    client: iam::Client,
+}
+
+impl IAM {
+    fn new(config: &SdkConfig) -> IAM {
+        IAM {
+            client: iam::Client::new(config),
+        }
+    }
+
+    async fn can_put_object(&self, role_arn: &str, object_arn: &str) -> Result<bool, iam::Error> {
+        let resp = self
+            .client
+            .simulate_principal_policy()
+            .policy_source_arn(role_arn)
+            .action_names("s3:PutObject")
+            .resource_arns(object_arn)
+            .send()
+            .await?;
+
+        Ok(resp
+            .evaluation_results()
+            .unwrap_or_default()
+            .iter()
+            .all(|result| result.eval_decision() == Some(&PolicyEvaluationDecisionType::Allowed)))
+    }
+}
+
+const ORG_KEY_POLICY_SID: &str = "rds-snapshot-share-organization";
+
+fn org_key_policy_statement(org_id: &str) -> Value {
+    json!({
+        "Sid": ORG_KEY_POLICY_SID,
+        "Effect": "Allow",
+        "Principal": { "AWS": "*" },
+        "Action": ["kms:Decrypt", "kms:DescribeKey", "kms:CreateGrant"],
+        "Resource": "*",
+        "Condition": { "StringEquals": { "aws:PrincipalOrgID": org_id } },
+    })
+}
+
+struct Organizations {
+    client: organizations::Client,
+}
+
+impl Organizations {
+    fn new(config: &SdkConfig) -> Organizations {
+        Organizations {
+            client: organizations::Client::new(config),
+        }
+    }
+
+    async fn list_inactive_accounts(
+        &self,
+    ) -> Result<HashMap<String, String>, organizations::Error> {
+        let paginator = self.client.list_accounts().into_paginator().send();
+        let pages = paginator.collect::<Result<Vec<_>, _>>().await?;
+
+        Ok(pages
+            .iter()
+            .flat_map(|page| page.accounts().unwrap_or_default())
+            .filter(|account| account.status() != Some(&AccountStatus::Active))
+            .filter_map(|account| {
+                let status = account.status().map(|status| status.as_str())?;
+
+                Some((account.id()?.to_string(), status.to_string()))
+            })
+            .collect())
+    }
+
+    async fn describe_organization_id(&self) -> Result<String, organizations::Error> {
+        let resp = self.client.describe_organization().send().await?;
+
+        Ok(resp.organization().unwrap().id().unwrap().to_string())
+    }
+}
+
+struct ServiceQuotas {
+    client: servicequotas::Client,
+}
+
+impl ServiceQuotas {
+    fn new(config: &SdkConfig) -> ServiceQuotas {
+        ServiceQuotas {
+            client: servicequotas::Client::new(config),
+        }
+    }
+
+    async fn get_rds_quota(&self, quota_code: &str) -> Result<f64, servicequotas::Error> {
+        let resp = self
+            .client
+            .get_service_quota()
+            .service_code("rds")
+            .quota_code(quota_code)
+            .send()
+            .await?;
+
+        Ok(resp
+            .quota()
+            .and_then(|quota| quota.value())
+            .unwrap_or_default())
+    }
+}
+
+struct AttributeChange {
+    time: String,
+    principal: String,
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+struct CloudTrail {
+    client: cloudtrail::Client,
+}
+
+impl CloudTrail {
+    fn new(config: &SdkConfig) -> CloudTrail {
+        CloudTrail {
+            client: cloudtrail::Client::new(config),
+        }
+    }
+
+    async fn lookup_attribute_changes(
+        &self,
+        db_type: &DatabaseType,
+        snapshot_id: &str,
+    ) -> Result<Vec<AttributeChange>, cloudtrail::Error> {
+        let (event_name, identifier_key) = match db_type {
+            DatabaseType::Cluster => (
+                "ModifyDBClusterSnapshotAttribute",
+                "dBClusterSnapshotIdentifier",
+            ),
+            DatabaseType::Database => ("ModifyDBSnapshotAttribute", "dBSnapshotIdentifier"),
+        };
+
+        // Looking up by resource keeps the throttled LookupEvents calls to this snapshot's
+        // events, rather than every attribute change in the account
+        let events = self
+            .lookup_events(LookupAttributeKey::ResourceName, snapshot_id, None)
+            .await?;
+
+        let strings = |value: &Value| -> Vec<String> {
+            value
+                .as_array()
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut changes: Vec<_> = events
+            .iter()
+            .filter(|event| event.event_name() == Some(event_name))
+            .filter_map(|event| {
+                let detail: Value = serde_json::from_str(event.cloud_trail_event()?).ok()?;
+                let parameters = &detail["requestParameters"];
+
+                if parameters[identifier_key].as_str()?.rsplit(':').next() != Some(snapshot_id) {
+                    return None;
+                }
+
+                let time = event.event_time()?.secs();
+
+                Some(AttributeChange {
+                    time: DateTime::from_timestamp(time, 0)
+                        .unwrap()
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string(),
+                    principal: detail["userIdentity"]["arn"]
+                        .as_str()
+                        .or(event.username())
+                        .unwrap_or_default()
+                        .to_string(),
+                    added: strings(&parameters["valuesToAdd"]),
+                    removed: strings(&parameters["valuesToRemove"]),
+                })
+            })
+            .collect();
+
+        changes.sort_by(|a, b| a.time.cmp(&b.time));
+
+        Ok(changes)
+    }
+
+    async fn lookup_modifications(
+        &self,
+        db_type: &DatabaseType,
+        identifier: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<AttributeChange>, cloudtrail::Error> {
+        let (event_name, identifier_key) = match db_type {
+            DatabaseType::Cluster => ("ModifyDBCluster", "dBClusterIdentifier"),
+            DatabaseType::Database => ("ModifyDBInstance", "dBInstanceIdentifier"),
+        };
+
+        let events = self
+            .lookup_events(LookupAttributeKey::EventName, event_name, Some(since))
+            .await?;
+
+        Ok(events
+            .iter()
+            .filter_map(|event| {
+                let detail: Value = serde_json::from_str(event.cloud_trail_event()?).ok()?;
+
+                if detail["requestParameters"][identifier_key].as_str()? != identifier {
+                    return None;
+                }
+
+                Some(AttributeChange {
+                    time: detail["eventTime"].as_str().unwrap_or_default().to_string(),
+                    principal: detail["userIdentity"]["arn"]
+                        .as_str()
+                        .or(event.username())
+                        .unwrap_or_default()
+                        .to_string(),
+                    added: vec![],
+                    removed: vec![],
+                })
+            })
+            .collect())
+    }
+
+    async fn lookup_events(
+        &self,
+        key: LookupAttributeKey,
+        value: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Event>, cloudtrail::Error> {
+        let attribute = LookupAttribute::builder()
+            .attribute_key(key)
+            .attribute_value(value)
+            .build();
+
+        let paginator = self
+            .client
+            .lookup_events()
+            .lookup_attributes(attribute)
+            .set_start_time(
+                since.map(|since| cloudtrail::types::DateTime::from_secs(since.timestamp())),
+            )
+            .into_paginator()
+            .items()
+            .send();
+
+        Ok(paginator.collect::<Result<Vec<_>, _>>().await?)
+    }
+}
+
+struct EC2 {
+    client: ec2::Client,
+}
+
+impl EC2 {
+    fn new(config: &SdkConfig) -> EC2 {
+        EC2 {
+            client: ec2::Client::new(config),
+        }
+    }
+
+    async fn describe_regions(&self) -> Result<Vec<String>, ec2::Error> {
+        let resp = self.client.describe_regions().send().await?;
+
+        Ok(resp
+            .regions()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|region| region.region_name().map(String::from))
+            .collect())
+    }
+
+    async fn describe_vpc_names(&self) -> Result<HashMap<String, String>, ec2::Error> {
+        let vpcs = self
+            .client
+            .describe_vpcs()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+
+        Ok(vpcs
+            .iter()
+            .filter_map(|vpc| {
+                let name = vpc
+                    .tags()
+                    .unwrap_or_default()
+                    .iter()
+                    .find(|tag| tag.key() == Some("Name"))
+                    .and_then(|tag| tag.value())?;
+
+                Some((vpc.vpc_id()?.to_string(), name.to_string()))
+            })
+            .collect())
+    }
+}
+
+struct SNS {
+    client: sns::Client,
+}
+
+impl SNS {
+    fn new(config: &SdkConfig) -> SNS {
+        SNS {
+            client: sns::Client::new(config),
+        }
+    }
+
+    async fn publish(
+        &self,
+        topic_arn: &str,
+        subject: &str,
+        message: String,
+    ) -> Result<(), sns::Error> {
+        self.client
+            .publish()
+            .topic_arn(topic_arn)
+            .subject(subject)
+            .message(message)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+struct ProgressNotification {
+    sns: SNS,
+    topic_arn: String,
+    percent: i32,
+}
+
+struct EventBridge {
+    client: eventbridge::Client,
+}
+
+impl EventBridge {
+    fn new(config: &SdkConfig) -> EventBridge {
+        EventBridge {
+            client: eventbridge::Client::new(config),
+        }
+    }
+
+    async fn put_share_event(
+        &self,
+        bus_name: &str,
+        summary: &ShareSummary,
+    ) -> Result<i32, eventbridge::Error> {
+        let entry = eventbridge::model::PutEventsRequestEntry::builder()
+            .event_bus_name(bus_name)
+            .source("rds-snapshot-share")
+            .detail_type("RDS Snapshot Shared")
+            .detail(serde_json::to_string(summary).unwrap())
+            .build();
+
+        let resp = self.client.put_events().entries(entry).send().await?;
+
+        Ok(resp.failed_entry_count())
+    }
+}
+
+#[cfg(feature = "slack")]
+struct Slack {
+    client: reqwest::Client,
+    webhook_url: String,
+}
+
+#[cfg(feature = "slack")]
+impl Slack {
+    fn new(webhook_url: String) -> Slack {
+        Slack {
+            client: reqwest::Client::new(),
+            webhook_url,
+        }
+    }
+
+    async fn post_summary(&self, summary: &ShareSummary) -> Result<(), reqwest::Error> {
+        let title = format!("RDS snapshot share {}", summary.status);
+        let field = |name: &str, value: &str| json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", name, value) });
+
+        let payload = json!({
+            "text": title,
+            "blocks": [
+                {
+                    "type": "header",
+                    "text": { "type": "plain_text", "text": title },
+                },
+                {
+                    "type": "section",
+                    "fields": [
+                        field("Database", &summary.db_identifier),
+                        field("Snapshot", &summary.snapshot_id),
+                        field("Accounts", &summary.account_ids.join(", ")),
+                        field("KMS key", &summary.kms_key_id),
+                        field("Status", &summary.status),
+                        field(
+                            "Duration",
+                            &format!("{:.1}s", summary.duration_ms as f64 / 1000.0)
+                        ),
+                    ],
+                },
+            ],
+        });
+
+        self.client
+            .post(&self.webhook_url)
+            .json(&payload)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "teams")]
+struct Teams {
+    client: reqwest::Client,
+    webhook_url: String,
+}
+
+#[cfg(feature = "teams")]
+impl Teams {
+    fn new(webhook_url: String) -> Teams {
+        Teams {
+            client: reqwest::Client::new(),
+            webhook_url,
+        }
+    }
+
+    async fn post_summary(
+        &self,
+        title: Option<String>,
+        summary: &ShareSummary,
+    ) -> Result<(), reqwest::Error> {
+        let title = title.unwrap_or_else(|| format!("RDS snapshot share {}", summary.status));
+        let fact = |name: &str, value: &str| json!({ "title": name, "value": value });
+
+        let payload = json!({
+            "type": "message",
+            "attachments": [
+                {
+                    "contentType": "application/vnd.microsoft.card.adaptive",
+                    "content": {
+                        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                        "type": "AdaptiveCard",
+                        "version": "1.4",
+                        "body": [
+                            {
+                                "type": "TextBlock",
+                                "size": "Large",
+                                "weight": "Bolder",
+                                "text": title,
+                            },
+                            {
+                                "type": "FactSet",
+                                "facts": [
+                                    fact("Database", &summary.db_identifier),
+                                    fact("Snapshot", &summary.snapshot_id),
+                                    fact("Accounts", &summary.account_ids.join(", ")),
+                                    fact("KMS key", &summary.kms_key_id),
+                                    fact("Status", &summary.status),
+                                    fact(
+                                        "Duration",
+                                        &format!("{:.1}s", summary.duration_ms as f64 / 1000.0)
+                                    ),
+                                ],
+                            },
+                        ],
+                    },
+                },
+            ],
+        });
+
+        self.client
+            .post(&self.webhook_url)
+            .json(&payload)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "pagerduty")]
+struct PagerDuty {
+    client: reqwest::Client,
+    routing_key: String,
+}
+
+#[cfg(feature = "pagerduty")]
+impl PagerDuty {
+    const EVENTS_URL: &'static str = "https://events.pagerduty.com/v2/enqueue";
+
+    fn new(routing_key: String) -> PagerDuty {
+        PagerDuty {
+            client: reqwest::Client::new(),
+            routing_key,
+        }
+    }
+
+    fn dedup_key(subject: &str) -> String {
+        format!("rds-snapshot-share/{}", subject)
+    }
+
+    async fn send(&self, event: Value) -> Result<(), reqwest::Error> {
+        self.client
+            .post(Self::EVENTS_URL)
+            .json(&event)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn trigger(&self, subject: &str, error: &str) -> Result<(), reqwest::Error> {
+        self.send(json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            "dedup_key": Self::dedup_key(subject),
+            "payload": {
+                "summary": format!("RDS snapshot share failed for {}", subject),
+                "source": "rds-snapshot-share",
+                "severity": "error",
+                "custom_details": {
+                    "snapshot": subject,
+                    "error": error,
+                    "remediation": [
+                        "Check the snapshot exists and is in the available state",
+                        "Check the caller may copy the snapshot and modify its restore attribute",
+                        "Check the KMS key policy allows RDS to use the key",
+                        "Re-run with --audit-mode to review the calls that would be made",
+                    ],
+                },
+            },
+        }))
+        .await
+    }
+
+    async fn resolve(&self, subject: &str) -> Result<(), reqwest::Error> {
+        self.send(json!({
+            "routing_key": self.routing_key,
+            "event_action": "resolve",
+            "dedup_key": Self::dedup_key(subject),
+        }))
+        .await
+    }
+}
+
+fn select(prompt: &str, choices: Vec<String>) -> Result<String, InquireError> {
+    let ans = Select::new(prompt, choices.clone()).prompt()?;
+
+    let index = choices.iter().position(|c| *c == ans).unwrap();
+
+    Ok(choices[index].clone())
+}
+
+fn select_rds(identifiers: Vec<String>) -> Result<String, InquireError> {
+    select("Please choose an RDS", identifiers)
+}
+
+fn select_rds_refreshing(
+    identifiers: &mut watch::Receiver<Vec<String>>,
+) -> Result<String, InquireError> {
+    select_refreshing("Please choose an RDS", identifiers)
+}
+
+fn select_keys(keys: Vec<Key>) -> Result<String, InquireError> {
+    let keys: HashMap<_, _> = keys.iter().map(|key| (key.label(), key)).collect();
+
+    let ans = select(
+        "Choose a KMS key to use for snapshot",
+        keys.keys().cloned().collect(),
+    )?;
+
+    Ok(keys[&ans].id.clone())
+}
+
+fn select_keys_refreshing(keys: &mut watch::Receiver<Vec<Key>>) -> Result<String, InquireError> {
+    loop {
+        let labels: HashMap<_, _> = keys
+            .borrow_and_update()
+            .iter()
+            .map(|key| (key.label(), key.id.clone()))
+            .collect();
+
+        let mut options: Vec<_> = labels.keys().cloned().collect();
+        options.sort();
+        options.insert(0, REFRESH_CHOICE.to_string());
+
+        let ans = select("Choose a KMS key to use for snapshot", options)?;
+
+        if ans != REFRESH_CHOICE {
+            return Ok(labels[&ans].clone());
+        }
+    }
+}
+
+fn select_snapshot(snapshots: Vec<String>) -> Result<String, InquireError> {
+    select("Select a snapshot to copy", snapshots)
+}
+
+fn select_snapshot_refreshing(
+    snapshots: &mut watch::Receiver<Vec<String>>,
+) -> Result<String, InquireError> {
+    select_refreshing("Select a snapshot to copy", snapshots)
+}
+
+fn confirm_database(identifier: &str) -> Result<bool, InquireError> {
+    Confirm::new(&format!("Use {}", identifier))
+        .with_default(true)
+        .prompt()
+}
+
+fn confirm_use_existing_copy(snapshot_id: &str) -> Result<bool, InquireError> {
+    Confirm::new(&format!(
+        "Share the existing {} instead of copying",
+        snapshot_id
+    ))
+    .with_default(true)
+    .prompt()
+}
+
+fn prompt_snapshot_id() -> Result<String, InquireError> {
+    Text::new("Choose a different snapshot identifier").prompt()
+}
+
+fn confirm_upgrade_in_progress(
+    identifier: &str,
+    details: &DatabaseDetails,
+) -> Result<bool, ShareError> {
+    let pending = details
+        .pending_engine_version
+        .as_ref()
+        .filter(|version| **version != details.engine_version);
+
+    let message = match (pending, details.status.as_str()) {
+        (Some(version), _) => format!(
+            "{} has an engine upgrade from {} to {} pending; its snapshots may be in an intermediate state",
+            identifier, details.engine_version, version
+        ),
+        (None, "upgrading") => format!(
+            "{} is upgrading; its snapshots may be in an intermediate state",
+            identifier
+        ),
+        _ => return Ok(true),
+    };
+
+    eprintln!("Warning: {}", message);
+
+    Ok(Confirm::new("Proceed anyway")
+        .with_default(false)
+        .prompt()?)
+}
+
+async fn confirm_recently_modified(
+    config: &SdkConfig,
+    db_type: &DatabaseType,
+    identifier: &str,
+    minutes: i64,
+) -> Result<bool, ShareError> {
+    let since = Utc::now() - chrono::Duration::minutes(minutes);
+    let modifications = CloudTrail::new(config)
+        .lookup_modifications(db_type, identifier, since)
+        .await?;
+
+    if modifications.is_empty() {
+        return Ok(true);
+    }
+
+    eprintln!(
+        "Warning: {} was modified {} time(s) in the last {} minutes; its configuration may still be changing",
+        identifier,
+        modifications.len(),
+        minutes
+    );
+
+    for modification in &modifications {
+        eprintln!("  {} by {}", modification.time, modification.principal);
+    }
+
+    Ok(Confirm::new("Proceed anyway")
+        .with_default(false)
+        .prompt()?)
+}
+
+fn prompt_account_ids() -> Result<Vec<String>, InquireError> {
+    let mut account_ids: Vec<String> = vec![];
+
+    loop {
+        let input =
+            Text::new("Account IDs to share with (comma-separated, empty or done to finish)")
+                .prompt()?;
+
+        if input.trim().is_empty() || input.trim() == "done" {
+            return Ok(account_ids);
+        }
+
+        for account_id in input.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            if account_id.len() != 12 || !account_id.chars().all(|c| c.is_ascii_digit()) {
+                eprintln!("{} is not a 12 digit account ID", account_id);
+            } else if !account_ids.iter().any(|id| id == account_id) {
+                account_ids.push(account_id.to_string());
+            }
+        }
+    }
+}
+
+fn limit_accounts(mut account_ids: Vec<String>, max_share_per_run: Option<usize>) -> Vec<String> {
+    let max = match max_share_per_run {
+        Some(max) if account_ids.len() > max => max,
+        _ => return account_ids,
+    };
+
+    account_ids.sort();
+    let remaining = account_ids.split_off(max);
+
+    eprintln!(
+        "Sharing with {} of {} accounts; re-run to share with the remaining accounts: {}",
+        account_ids.len(),
+        account_ids.len() + remaining.len(),
+        remaining.join(" ")
+    );
+
+    account_ids
+}
+
+fn read_snapshot_arns() -> Vec<String> {
+    io::stdin()
+        .lock()
+        .lines()
+        .map(|line| line.unwrap())
+        .flat_map(|line| {
+            line.split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+async fn share_snapshot_arns(
+    arns: Vec<String>,
+    account_ids: Vec<String>,
+    config: &SdkConfig,
+    rds_endpoint_url: Option<&str>,
+    audit: &mut Option<AuditTrail>,
+) -> bool {
+    let mut clients: HashMap<String, RDS> = HashMap::new();
+    let mut all_shared = true;
+
+    for arn in arns {
+        let snapshot = match arn.parse::<SnapshotArn>() {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                println!("{}: failed ({})", arn, err);
+                all_shared = false;
+                continue;
+            }
+        };
+
+        if !clients.contains_key(&snapshot.region) {
+            let endpoint_url = regional_endpoint_url(config, &snapshot.region, rds_endpoint_url);
+            let config = aws_config::from_env()
+                .region(Region::new(snapshot.region.clone()))
+                .load()
+                .await;
+
+            clients.insert(
+                snapshot.region.clone(),
+                RDS::with_endpoint(&config, endpoint_url),
+            );
+        }
+
+        if let Some(audit) = audit.as_mut() {
+            audit.share_snapshot(&snapshot.db_type, &snapshot.snapshot_id, &account_ids);
+            continue;
+        }
+
+        let rds = &clients[&snapshot.region];
+
+        match rds
+            .share_snapshot(&snapshot.db_type, snapshot.snapshot_id, account_ids.clone())
+            .await
+        {
+            Ok(shared_with) => println!("{}: shared with {}", arn, shared_with.join(",")),
+            Err(err) => {
+                println!("{}: failed ({})", arn, err);
+                all_shared = false;
+            }
+        }
+    }
+
+    all_shared
+}
+
+async fn wait_until_available(
+    rds: &RDS,
+    db_type: &DatabaseType,
+    identifier: &str,
+) -> Result<(), rds::Error> {
+    loop {
+        let status = match db_type {
+            DatabaseType::Cluster => rds.describe_cluster_status(identifier.to_string()).await?,
+            DatabaseType::Database => rds.describe_instance_status(identifier.to_string()).await?,
+        };
+
+        println!("{} {}: {}", db_type, identifier, status);
+
+        if status == "available" {
+            return Ok(());
+        }
+
+        sleep(Duration::from_secs(30)).await;
+    }
+}
+
+async fn wait_for_snapshot(
+    rds: &RDS,
+    db_type: &DatabaseType,
+    snapshot_id: &str,
+    progress_file: Option<&Path>,
+    notification: Option<&ProgressNotification>,
+) -> Result<SnapshotInfo, rds::Error> {
+    let mut notified = false;
+
+    loop {
+        let snapshot = rds
+            .describe_snapshot(db_type, snapshot_id.to_string())
+            .await?;
+
+        let progress = format!(
+            "{}: {} ({}%)",
+            snapshot.id, snapshot.status, snapshot.percent_progress
+        );
+
+        eprintln!("{}", progress);
+
+        if let Some(path) = progress_file {
+            fs::write(path, format!("{}\n", progress)).unwrap();
+        }
+
+        if let Some(notification) = notification {
+            if !notified && snapshot.percent_progress >= notification.percent {
+                if let Err(err) = notification
+                    .sns
+                    .publish(
+                        &notification.topic_arn,
+                        "RDS snapshot copy progress",
+                        progress.clone(),
+                    )
+                    .await
+                {
+                    eprintln!("Warning: could not publish copy progress to SNS: {}", err);
+                }
+
+                notified = true;
+            }
+        }
+
+        if snapshot.status == "available" || snapshot.status == "failed" {
+            return Ok(snapshot);
+        }
+
+        sleep(Duration::from_secs(30)).await;
+    }
+}
+
+fn report_check(name: &str, result: Result<String, String>) -> bool {
+    match result {
+        Ok(detail) => {
+            println!("PASS {}: {}", name, detail);
+            true
+        }
+        Err(detail) => {
+            println!("FAIL {}: {}", name, detail);
+            false
+        }
+    }
+}
+
+async fn simulate_restore(
+    config: &SdkConfig,
+    args: SimulateRestoreArgs,
+    rds_endpoint_url: Option<&str>,
+    kms_endpoint_url: Option<&str>,
+) -> bool {
+    let snapshot_arn = match args.snapshot_arn.parse::<SnapshotArn>() {
+        Ok(snapshot_arn) => snapshot_arn,
+        Err(err) => {
+            eprintln!("{}", err);
+            return false;
+        }
+    };
+
+    let region = Region::new(snapshot_arn.region.clone());
+    let provider = AssumeRoleProvider::builder(args.target_role_arn)
+        .session_name("rds-snapshot-share-simulate-restore")
+        .region(region.clone())
+        .build(config.credentials_provider().unwrap().clone());
+    let target_config = aws_config::from_env()
+        .region(region)
+        .credentials_provider(provider)
+        .load()
+        .await;
+
+    let rds = RDS::with_endpoint(
+        &target_config,
+        regional_endpoint_url(config, &snapshot_arn.region, rds_endpoint_url),
+    );
+    let kms = KMS::with_endpoint(
+        &target_config,
+        regional_endpoint_url(config, &snapshot_arn.region, kms_endpoint_url),
+    );
+
+    let snapshot = rds
+        .describe_shared_snapshot(&snapshot_arn.db_type, args.snapshot_arn.clone())
+        .await;
+
+    report_check(
+        "snapshot visible",
+        match &snapshot {
+            Ok(Some(snapshot)) => Ok(format!("{} is {}", snapshot.id, snapshot.status)),
+            Ok(None) => Err(format!(
+                "{} is not shared with this account",
+                args.snapshot_arn
+            )),
+            Err(err) => Err(err.to_string()),
+        },
+    );
+
+    let snapshot = match snapshot {
+        Ok(Some(snapshot)) => snapshot,
+        _ => return false,
+    };
+
+    let instance_classes = rds
+        .describe_orderable_instance_classes(
+            snapshot.engine.clone(),
+            snapshot.engine_version.clone(),
+            args.instance_class,
+        )
+        .await;
+
+    let orderable = report_check(
+        "instance class available",
+        match instance_classes {
+            Ok(classes) if classes.is_empty() => Err(format!(
+                "no orderable instance classes for {} {}",
+                snapshot.engine, snapshot.engine_version
+            )),
+            Ok(mut classes) => {
+                classes.sort();
+                classes.dedup();
+                Ok(format!("{} classes, e.g. {}", classes.len(), classes[0]))
+            }
+            Err(err) => Err(err.to_string()),
+        },
+    );
+
+    let key_access = report_check(
+        "KMS key access",
+        match &snapshot.kms_key_id {
+            Some(key_id) => match kms.describe_key(key_id.clone()).await {
+                Ok(key) => Ok(format!("{} is {:?}", key_id, key.key_state().unwrap())),
+                Err(err) => Err(format!("{}: {}", key_id, err)),
+            },
+            None => Ok("snapshot is not encrypted".to_string()),
+        },
+    );
+
+    orderable && key_access
+}
+
+async fn print_kms_key_usage(rds: &RDS, kms: &KMS) -> Result<(), rds::Error> {
+    let keys = kms.list_keys(&KeyManager::Customer).await.unwrap();
+    let mut usage: HashMap<String, (usize, i32)> = HashMap::new();
+
+    // Automated snapshots are counted too, since rotating or disabling a key affects them as well
+    let mut snapshots = rds
+        .stream_db_cluster_snapshots(None, None)
+        .chain(rds.stream_db_snapshots(None, None));
+
+    while let Some(record) = snapshots.next().await {
+        let record = record?;
+
+        if let Some(key_arn) = record.kms_key_id {
+            let key_id = key_arn.rsplit('/').next().unwrap().to_string();
+            let (count, size) = usage.entry(key_id).or_default();
+
+            *count += 1;
+            *size += record.allocated_storage_gib;
+        }
+    }
+
+    let rows: Vec<_> = keys
+        .iter()
+        .map(|key| {
+            let (count, size) = usage.get(&key.id).copied().unwrap_or_default();
+
+            vec![
+                key.id.clone(),
+                key.alias.clone().unwrap_or_default(),
+                count.to_string(),
+                size.to_string(),
+            ]
+        })
+        .collect();
+
+    print_table(
+        &["Key ID", "Alias", "Snapshot Count", "Total Size GiB"],
+        &rows,
+    );
+
+    Ok(())
+}
+
+async fn watch_all(rds: &RDS, args: WatchAllArgs) -> Result<bool, rds::Error> {
+    let mut snapshot_ids = args.snapshot_ids;
+
+    if let Some(path) = &args.accounts_file {
+        let contents = fs::read_to_string(path).unwrap();
+
+        snapshot_ids.extend(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from),
+        );
+    }
+
+    let width = snapshot_ids
+        .iter()
+        .map(String::len)
+        .max()
+        .unwrap_or_default();
+    let style = ProgressStyle::with_template(&format!(
+        "{{prefix:{}}} [{{bar:40}}] {{pos:>3}}% {{msg:10}} {{elapsed_precise}}",
+        width
+    ))
+    .unwrap()
+    .progress_chars("=> ");
+
+    let progress = MultiProgress::new();
+    let mut watches = JoinSet::new();
+
+    for snapshot_id in snapshot_ids {
+        let bar = progress.add(ProgressBar::new(100));
+        bar.set_style(style.clone());
+        bar.set_prefix(snapshot_id.clone());
+        bar.enable_steady_tick(Duration::from_secs(1));
+
+        let rds = rds.clone();
+        let db_type = args.db_type.clone();
+
+        watches.spawn(async move {
+            loop {
+                let snapshot = rds.describe_snapshot(&db_type, snapshot_id.clone()).await?;
+
+                bar.set_position(snapshot.percent_progress as u64);
+                bar.set_message(snapshot.status.clone());
+
+                if snapshot.status == "available" || snapshot.status == "failed" {
+                    bar.finish();
+
+                    return Ok::<_, rds::Error>(snapshot.status == "available");
+                }
+
+                sleep(Duration::from_secs(30)).await;
+            }
+        });
+    }
+
+    let mut available = true;
+
+    while let Some(watch) = watches.join_next().await {
+        available &= watch.unwrap()?;
+    }
+
+    Ok(available)
+}
+
+async fn batch_yaml(
+    config: &SdkConfig,
+    args: BatchYamlArgs,
+    rds_endpoint_url: Option<&str>,
+    kms_endpoint_url: Option<&str>,
+) -> bool {
+    let contents = match fs::read_to_string(&args.file) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("{}: {}", args.file.display(), err);
+            process::exit(1);
+        }
+    };
+    let manifest: BatchManifest = match serde_yaml::from_str(&contents) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            eprintln!("{}: {}", args.file.display(), err);
+            process::exit(1);
+        }
+    };
+
+    let default_region = config
+        .region()
+        .map(|region| region.to_string())
+        .unwrap_or_default();
+
+    let permits = Arc::new(Semaphore::new(args.parallelism.max(1)));
+    let mut runs = JoinSet::new();
+
+    for job in manifest.jobs {
+        let regions = match job.target_regions.is_empty() {
+            true => vec![default_region.clone()],
+            false => job.target_regions.clone(),
+        };
+
+        for region in regions {
+            let permit = permits.clone().acquire_owned().await.unwrap();
+            let job = job.clone();
+            let rds_endpoint_url =
+                regional_endpoint_url(config, &region, rds_endpoint_url).map(str::to_string);
+            let kms_endpoint_url =
+                regional_endpoint_url(config, &region, kms_endpoint_url).map(str::to_string);
+
+            runs.spawn(async move {
+                let config = aws_config::from_env()
+                    .region(Region::new(region.clone()))
+                    .load()
+                    .await;
+
+                let outcome = run_batch_job(
+                    &config,
+                    &job,
+                    rds_endpoint_url.as_deref(),
+                    kms_endpoint_url.as_deref(),
+                )
+                .await;
+                drop(permit);
+
+                let (status, snapshot_id, shared_with, error) = match outcome {
+                    Ok((snapshot_id, shared_with)) => {
+                        ("shared", Some(snapshot_id), shared_with, None)
+                    }
+                    Err(err) => ("failed", None, vec![], Some(err)),
+                };
+
+                eprintln!("{} ({}): {}", job.db_identifier, region, status);
+
+                BatchJobResult {
+                    db_identifier: job.db_identifier,
+                    region,
+                    status: status.to_string(),
+                    snapshot_id,
+                    shared_with,
+                    error,
+                }
+            });
+        }
+    }
+
+    let mut results = vec![];
+
+    while let Some(result) = runs.join_next().await {
+        results.push(result.unwrap());
+    }
+
+    results.sort_by(|a, b| (&a.db_identifier, &a.region).cmp(&(&b.db_identifier, &b.region)));
+
+    fs::write(&args.results_file, serde_yaml::to_string(&results).unwrap()).unwrap();
+
+    let shared = results
+        .iter()
+        .filter(|result| result.error.is_none())
+        .count();
+
+    println!(
+        "{} of {} job(s) shared; results written to {}",
+        shared,
+        results.len(),
+        args.results_file.display()
+    );
+
+    shared == results.len()
+}
+
+async fn run_batch_job(
+    config: &SdkConfig,
+    job: &BatchJob,
+    rds_endpoint_url: Option<&str>,
+    kms_endpoint_url: Option<&str>,
+) -> Result<(String, Vec<String>), String> {
+    let rds = RDS::with_endpoint(config, rds_endpoint_url);
+    let kms = KMS::with_endpoint(config, kms_endpoint_url);
+    let db_type = job.db_type.clone().unwrap_or(DatabaseType::Database);
+    let identifier = Some(job.db_identifier.clone());
+    // Automated snapshots cannot be shared, so only available manual snapshots are candidates
+    let manual = Some("manual".to_string());
+
+    let records = match db_type {
+        DatabaseType::Cluster => {
+            rds.stream_db_cluster_snapshots(identifier, manual)
+                .collect::<Result<Vec<_>, _>>()
+                .await
+        }
+        DatabaseType::Database => {
+            rds.stream_db_snapshots(identifier, manual)
+                .collect::<Result<Vec<_>, _>>()
+                .await
+        }
+    }
+    .map_err(|err| err.to_string())?;
+
+    let latest = records
+        .into_iter()
+        .filter(|record| record.status == "available")
+        .filter(|record| match &job.snapshot_filter {
+            Some(pattern) => record.snapshot_id.contains(pattern.as_str()),
+            None => true,
+        })
+        .max_by(|a, b| a.created.cmp(&b.created))
+        .ok_or_else(|| {
+            format!(
+                "no available manual snapshot of {} matches the filter",
+                job.db_identifier
+            )
+        })?;
+
+    let key = kms
+        .describe_key(job.kms_key_id.clone())
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if key.key_manager() == Some(&KeyManagerType::Aws) {
+        return Err(format!(
+            "{} is an AWS managed key, whose policy cannot grant other accounts access",
+            job.kms_key_id
+        ));
+    }
+
+    let share_id = match latest.kms_key_id.as_deref() == key.arn() {
+        true => latest.snapshot_id,
+        false => {
+            let copy_snapshot_id =
+                default_copy_snapshot_id(&latest.snapshot_id, None, RDS_MAX_IDENTIFIER_LENGTH);
+
+            let existing = rds
+                .find_snapshot(&db_type, copy_snapshot_id.clone())
+                .await
+                .map_err(|err| err.to_string())?;
+
+            // Jobs run unattended, so an available copy is shared rather than prompting
+            if let Some(existing) = existing {
+                match copy_collision(&existing, &db_type) {
+                    None => existing.id,
+                    Some(reason) => return Err(reason),
+                }
+            } else {
+                let copy = rds
+                    .copy_snapshot(
+                        &db_type,
+                        latest.snapshot_id.clone(),
+                        copy_snapshot_id,
+                        key.arn().map(String::from),
+                        vec![db_identifier_tag(&job.db_identifier)],
+                    )
+                    .await
+                    .map_err(|err| err.to_string())?;
+
+                let copy = wait_for_snapshot(&rds, &db_type, &copy.id, None, None)
+                    .await
+                    .map_err(|err| err.to_string())?;
+
+                if copy.status == "failed" {
+                    return Err(format!("copy {} failed", copy.id));
+                }
+
+                copy.id
+            }
+        }
+    };
+
+    let shared_with = rds
+        .share_snapshot(&db_type, share_id.clone(), job.target_accounts.clone())
+        .await
+        .map_err(|err| err.to_string())?;
+
+    Ok((share_id, shared_with))
+}
+
+async fn copy_snapshot_attributes(
+    rds: &RDS,
+    args: CopyAttributesArgs,
+    audit: &mut Option<AuditTrail>,
+) -> Result<(), ShareError> {
+    let (source, dest) = join!(
+        rds.describe_restore_accounts(&args.db_type, args.source_snapshot_id.clone()),
+        rds.describe_restore_accounts(&args.db_type, args.dest_snapshot_id.clone()),
+    );
+    let (source, dest) = (source?, dest?);
+
+    let add: Vec<_> = source
+        .iter()
+        .filter(|id| !dest.contains(id))
+        .cloned()
+        .collect();
+    let remove: Vec<_> = match args.merge {
+        true => vec![],
+        false => dest
+            .iter()
+            .filter(|id| !source.contains(id))
+            .cloned()
+            .collect(),
+    };
+
+    if add.is_empty() && remove.is_empty() {
+        eprintln!(
+            "{} is already shared with the same accounts as {}",
+            args.dest_snapshot_id, args.source_snapshot_id
+        );
+        return Ok(());
+    }
+
+    if let Some(audit) = audit.as_mut() {
+        audit.modify_restore_accounts(&args.db_type, &args.dest_snapshot_id, &add, &remove);
+
+        if args.snapshot_tag_accounts_count {
+            audit.tag_shared_accounts(&args.dest_snapshot_id);
+        }
+
+        return Ok(());
+    }
+
+    let accounts = rds
+        .modify_restore_accounts(&args.db_type, args.dest_snapshot_id.clone(), add, remove)
+        .await?;
+
+    if args.snapshot_tag_accounts_count {
+        rds.tag_shared_accounts(&args.db_type, args.dest_snapshot_id.clone(), accounts.len())
+            .await?;
+    }
+
+    println!(
+        "{} is shared with: {}",
+        args.dest_snapshot_id,
+        accounts.join(", ")
+    );
+
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+fn print_snapshot_header(format: &ListFormat, with_region: bool) {
+    match format {
+        ListFormat::Table => println!(
+            "{}{:<24} {:<40} {:<20} {:>8} {:<9} SHARING",
+            match with_region {
+                true => format!("{:<16} ", "REGION"),
+                false => String::new(),
+            },
+            "DB IDENTIFIER",
+            "SNAPSHOT ID",
+            "CREATED",
+            "SIZE GIB",
+            "ENCRYPTED"
+        ),
+        ListFormat::Json => (),
+        ListFormat::Csv => println!(
+            "{}db_type,db_identifier,snapshot_id,created,allocated_storage_gib,encrypted,shared_with",
+            match with_region {
+                true => "region,",
+                false => "",
+            }
+        ),
+    }
+}
+
+fn print_snapshot_record(record: &SnapshotRecord, format: &ListFormat) {
+    match format {
+        ListFormat::Table => println!(
+            "{}{:<24} {:<40} {:<20} {:>8} {:<9} {}",
+            record
+                .region
+                .as_ref()
+                .map(|region| format!("{:<16} ", region))
+                .unwrap_or_default(),
+            record.db_identifier,
+            record.snapshot_id,
+            record.created,
+            record.allocated_storage_gib,
+            record.encrypted,
+            record.sharing()
+        ),
+        ListFormat::Json => println!("{}", serde_json::to_string(record).unwrap()),
+        ListFormat::Csv => println!(
+            "{}",
+            record
+                .region
+                .iter()
+                .cloned()
+                .chain([
+                    record.db_type.to_string(),
+                    record.db_identifier.clone(),
+                    record.snapshot_id.clone(),
+                    record.created.clone(),
+                    record.allocated_storage_gib.to_string(),
+                    record.encrypted.to_string(),
+                    record.shared_with.join(";"),
+                ])
+                .map(|field| csv_field(&field))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    }
+}
+
+async fn collect_region_snapshots(
+    region: String,
+    rds_endpoint_url: Option<String>,
+) -> Result<Vec<SnapshotRecord>, rds::Error> {
+    let config = aws_config::from_env()
+        .region(Region::new(region.clone()))
+        .load()
+        .await;
+    let rds = RDS::with_endpoint(&config, rds_endpoint_url.as_deref());
+
+    let manual = Some("manual".to_string());
+    let mut snapshots = rds
+        .stream_db_cluster_snapshots(None, manual.clone())
+        .chain(rds.stream_db_snapshots(None, manual));
+    let mut records = vec![];
+
+    while let Some(record) = snapshots.next().await {
+        let mut record = record?;
+
+        record.region = Some(region.clone());
+        record.shared_with = rds
+            .describe_restore_accounts(&record.db_type, record.snapshot_id.clone())
+            .await?;
+
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+async fn inventory(
+    config: &SdkConfig,
+    format: &ListFormat,
+    rds_endpoint_url: Option<&str>,
+) -> Result<(), rds::Error> {
+    let regions = EC2::new(config).describe_regions().await.unwrap();
+    let mut queries = JoinSet::new();
+
+    for region in regions {
+        let endpoint_url = regional_endpoint_url(config, &region, rds_endpoint_url);
+
+        queries.spawn(collect_region_snapshots(
+            region,
+            endpoint_url.map(str::to_string),
+        ));
+    }
+
+    print_snapshot_header(format, true);
+
+    while let Some(records) = queries.join_next().await {
+        for record in records.unwrap()? {
+            print_snapshot_record(&record, format);
+        }
+    }
+
+    Ok(())
+}
+
+async fn list_all_snapshots(rds: &RDS, format: &ListFormat) -> Result<(), rds::Error> {
+    let manual = Some("manual".to_string());
+    let clusters = rds.stream_db_cluster_snapshots(None, manual.clone());
+    let instances = rds.stream_db_snapshots(None, manual);
+
+    print_snapshot_records(
+        rds,
+        clusters.chain(instances),
+        format,
+        &SnapshotFilter::default(),
+    )
+    .await
+}
+
+async fn list_snapshots(rds: &RDS, args: ListSnapshotsArgs) -> Result<(), rds::Error> {
+    let filter = SnapshotFilter {
+        backup_target: args.backup_target,
+        max_snapshots: args.max_snapshots.map(|max| max as usize),
+    };
+    let page = args
+        .snapshot_list_page
+        .map(|page| (page as usize, args.snapshot_list_page_size as usize));
+
+    match args.db_type {
+        DatabaseType::Cluster => {
+            let snapshots =
+                rds.stream_db_cluster_snapshots(Some(args.db_identifier), args.snapshot_type);
+
+            print_snapshot_page(rds, snapshots, &args.format, &filter, page).await
+        }
+        DatabaseType::Database => {
+            let snapshots = rds.stream_db_snapshots(Some(args.db_identifier), args.snapshot_type);
+
+            print_snapshot_page(rds, snapshots, &args.format, &filter, page).await
+        }
+    }
+}
+
+async fn print_snapshot_page(
+    rds: &RDS,
+    snapshots: impl Stream<Item = Result<SnapshotRecord, rds::Error>> + Unpin,
+    format: &ListFormat,
+    filter: &SnapshotFilter,
+    page: Option<(usize, usize)>,
+) -> Result<(), rds::Error> {
+    let (page, page_size) = match page {
+        Some(page) => page,
+        None => return print_snapshot_records(rds, snapshots, format, filter).await,
+    };
+
+    let mut records: Vec<_> = snapshots.collect::<Result<_, _>>().await?;
+
+    if let Some(target) = &filter.backup_target {
+        records.retain(|record| record.snapshot_target == target.api_value());
+    }
+
+    let total = records.len();
+    let pages = total.div_ceil(page_size).max(1);
+    let header = format!("Page {} of {} ({} total snapshots)", page, pages, total);
+
+    match format {
+        ListFormat::Table => println!("{}", header),
+        _ => eprintln!("{}", header),
+    }
+
+    let records = records
+        .into_iter()
+        .skip((page - 1) * page_size)
+        .take(page_size)
+        .map(Ok);
+
+    print_snapshot_records(
+        rds,
+        tokio_stream::iter(records),
+        format,
+        &SnapshotFilter::default(),
+    )
+    .await
+}
+
+async fn print_snapshot_records(
+    rds: &RDS,
+    mut snapshots: impl Stream<Item = Result<SnapshotRecord, rds::Error>> + Unpin,
+    format: &ListFormat,
+    filter: &SnapshotFilter,
+) -> Result<(), rds::Error> {
+    print_snapshot_header(format, false);
+
+    let mut printed = 0;
+
+    while !filter.is_full(printed) {
+        let mut record = match snapshots.next().await {
+            Some(record) => record?,
+            None => break,
+        };
+
+        if let Some(target) = &filter.backup_target {
+            if record.snapshot_target != target.api_value() {
+                continue;
+            }
+        }
+
+        record.shared_with = rds
+            .describe_restore_accounts(&record.db_type, record.snapshot_id.clone())
+            .await?;
+
+        print_snapshot_record(&record, format);
+        printed += 1;
+    }
+
+    Ok(())
+}
+
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let widths: Vec<_> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain([header.len()])
+                .max()
+                .unwrap()
+        })
+        .collect();
+
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", line(headers.to_vec()));
+    println!(
+        "{}",
+        widths
+            .iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<_>>()
+            .join("-+-")
+    );
+
+    for row in rows {
+        println!("{}", line(row.iter().map(String::as_str).collect()));
+    }
+}
+
+fn print_attribute_comparison(a: &[String], b: &[String]) {
+    let mut only_a: Vec<_> = a.iter().filter(|id| !b.contains(id)).cloned().collect();
+    let mut only_b: Vec<_> = b.iter().filter(|id| !a.contains(id)).cloned().collect();
+    let mut both: Vec<_> = a.iter().filter(|id| b.contains(id)).cloned().collect();
+
+    only_a.sort();
+    only_b.sort();
+    both.sort();
+
+    let len = only_a.len().max(only_b.len()).max(both.len());
+
+    let rows: Vec<_> = (0..len)
+        .map(|i| {
+            [&only_a, &only_b, &both]
+                .iter()
+                .map(|column| column.get(i).cloned().unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    print_table(&["Only in A", "Only in B", "In both"], &rows);
+}
+
+fn print_option_groups(snapshot: &SnapshotInfo, groups: &[OptionGroup]) {
+    let groups: Vec<_> = groups
+        .iter()
+        .filter(|group| {
+            let major = group.major_engine_version().unwrap_or_default();
+
+            snapshot.option_group_name.is_some() || snapshot.engine_version.starts_with(major)
+        })
+        .collect();
+
+    if groups.is_empty() {
+        eprintln!(
+            "No option groups found for {} {}",
+            snapshot.engine, snapshot.engine_version
+        );
+        return;
+    }
+
+    for group in groups {
+        eprintln!(
+            "Option group {} ({} {})",
+            group.option_group_name().unwrap_or_default(),
+            group.engine_name().unwrap_or_default(),
+            group.major_engine_version().unwrap_or_default()
+        );
+
+        let options = group.options().unwrap_or_default();
+
+        if options.is_empty() {
+            eprintln!("  no options");
+        }
+
+        for option in options {
+            let mut flags = vec![];
+
+            if option.persistent() {
+                flags.push("persistent");
+            }
+            if option.permanent() {
+                flags.push("permanent");
+            }
+
+            eprintln!(
+                "  {}{}",
+                option.option_name().unwrap_or_default(),
+                match flags.is_empty() {
+                    true => String::new(),
+                    false => format!(" [{}]", flags.join(", ")),
+                }
+            );
+
+            if let Some(port) = option.port() {
+                eprintln!("    port = {}", port);
+            }
+
+            for setting in option.option_settings().unwrap_or_default() {
+                if let Some(value) = setting.value() {
+                    eprintln!("    {} = {}", setting.name().unwrap_or_default(), value);
+                }
+            }
+        }
+    }
+}
+
+async fn check_snapshot_quota(
+    config: &SdkConfig,
+    rds: &RDS,
+    db_type: &DatabaseType,
+) -> Result<(i64, f64), ShareError> {
+    let (name, attribute, quota_code) = match db_type {
+        DatabaseType::Cluster => (
+            "Manual DB cluster snapshots",
+            "ManualClusterSnapshots",
+            "L-7B6409FD",
+        ),
+        DatabaseType::Database => (
+            "Manual DB instance snapshots",
+            "ManualSnapshots",
+            "L-272F1212",
+        ),
+    };
+
+    let limit = ServiceQuotas::new(config).get_rds_quota(quota_code).await?;
+    let used = rds
+        .describe_account_quota_usage()
+        .await?
+        .get(attribute)
+        .copied()
+        .unwrap_or_default();
+
+    eprintln!("{}: {} of {} used", name, used, limit);
+
+    Ok((used, limit))
+}
+
+fn backup_window_active(window: &str, now: DateTime<Utc>) -> bool {
+    let minutes = |time: &str| {
+        let (hours, minutes) = time.split_once(':')?;
+
+        Some(hours.parse::<u32>().ok()? * 60 + minutes.parse::<u32>().ok()?)
+    };
+
+    let (start, end) = match window.split_once('-') {
+        Some((start, end)) => match (minutes(start), minutes(end)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return false,
+        },
+        None => return false,
+    };
+
+    let now = now.hour() * 60 + now.minute();
+
+    match start <= end {
+        true => start <= now && now < end,
+        false => now >= start || now < end,
+    }
+}
+
+fn warn_backup_settings(identifier: &str, details: &DatabaseDetails) {
+    if details.backup_retention_period == 0 {
+        eprintln!(
+            "Warning: automated backups are disabled for {}; a manual snapshot is its only recovery point",
+            identifier
+        );
+    }
+
+    if let Some(window) = &details.preferred_backup_window {
+        if backup_window_active(window, Utc::now()) {
+            eprintln!(
+                "Warning: the preferred backup window for {} ({} UTC) is in progress; a manual snapshot may conflict with the automated backup",
+                identifier, window
+            );
+        }
+    }
+}
+
+fn aurora_mysql_major_version(engine_version: &str) -> Option<u32> {
+    let (_, aurora_version) = engine_version.split_once(".mysql_aurora.")?;
+
+    aurora_version.split('.').next()?.parse().ok()
+}
+
+fn warn_aurora_version(snapshot_version: &str, target_version: Option<&str>) {
+    let source = aurora_mysql_major_version(snapshot_version);
+    let target = target_version.and_then(aurora_mysql_major_version);
+
+    match (source, target) {
+        (Some(source), Some(target)) if source != target => eprintln!(
+            "Warning: {} is an Aurora MySQL {}.x snapshot and cannot be restored as Aurora MySQL {}.x; pass an {}.x --engine-version and upgrade the restored cluster",
+            snapshot_version, source, target, source
+        ),
+        (Some(2), None) => eprintln!(
+            "Snapshot is Aurora MySQL 2.x (MySQL 5.7 compatible, {}); pass --engine-version to restore to a specific 2.x release",
+            snapshot_version
+        ),
+        _ => (),
+    }
+}
+
+fn org_policy_statement(db_type: &DatabaseType, snapshot_arn: &str) -> Value {
+    let action = match db_type {
+        DatabaseType::Cluster => "rds:ModifyDBClusterSnapshotAttribute",
+        DatabaseType::Database => "rds:ModifyDBSnapshotAttribute",
+    };
+    let sid: String = snapshot_arn
+        .rsplit(':')
+        .next()
+        .unwrap()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+
+    json!({
+        "Sid": format!("AllowShare{}", sid),
+        "Effect": "Allow",
+        "Action": action,
+        "Resource": snapshot_arn,
+    })
+}
+
+fn warn_lifecycle_policy(snapshot: &SnapshotInfo) {
+    if snapshot.snapshot_type == "awsbackup" {
+        eprintln!(
+            "Warning: {} was created by AWS Backup, whose backup plan decides when it is deleted; do not delete it by hand",
+            snapshot.id
+        );
+    }
+
+    let lifecycle_tags: Vec<_> = snapshot
+        .tags
+        .iter()
+        .filter(|tag| tag.starts_with("aws:backup:") || tag.starts_with("aws:dlm:"))
+        .cloned()
+        .collect();
+
+    if !lifecycle_tags.is_empty() {
+        eprintln!(
+            "Warning: {} carries lifecycle management tags ({}) and may be deleted automatically",
+            snapshot.id,
+            lifecycle_tags.join(", ")
+        );
+    }
+}
+
+enum CopyTarget {
+    New(String),
+    Existing(String),
+}
+
+async fn check_copy_collision(
+    rds: &RDS,
+    db_type: &DatabaseType,
+    snapshot_id: String,
+) -> Result<CopyTarget, ShareError> {
+    let mut snapshot_id = snapshot_id;
+    // Without a terminal there is nobody to ask, so the collision is reported instead
+    let interactive = io::stdin().is_terminal();
+
+    loop {
+        let existing = match rds.find_snapshot(db_type, snapshot_id.clone()).await? {
+            Some(existing) => existing,
+            None => return Ok(CopyTarget::New(snapshot_id)),
+        };
+
+        match copy_collision(&existing, db_type) {
+            None if !interactive => {
+                return Err(ShareError(format!(
+                    "Snapshot {} already exists and is available; re-run with -s {} to share it, or choose another name with --new-snapshot-id",
+                    existing.id, existing.id
+                )))
+            }
+            None => {
+                eprintln!("Snapshot {} already exists and is available", existing.id);
+
+                if confirm_use_existing_copy(&existing.id)? {
+                    return Ok(CopyTarget::Existing(existing.id));
+                }
+            }
+            Some(reason) if !interactive => return Err(ShareError(reason)),
+            Some(reason) => eprintln!("{}", reason),
+        }
+
+        snapshot_id = prompt_snapshot_id()?;
+    }
+}
+
+/// Why an existing snapshot keeps its name from being used for a copy, or None when it is
+/// available and can be shared instead
+fn copy_collision(existing: &SnapshotInfo, db_type: &DatabaseType) -> Option<String> {
+    match existing.status.as_str() {
+        "available" => None,
+        "creating" | "copying" => Some(format!(
+            "Snapshot {} is still being created ({}%); follow it with `copy-progress -s {} -t {}` and re-run with -s {} once it is available",
+            existing.id, existing.percent_progress, existing.id, db_type, existing.id
+        )),
+        "failed" => Some(format!(
+            "Snapshot {} exists but failed; delete it before reusing the name",
+            existing.id
+        )),
+        status => Some(format!(
+            "Snapshot {} already exists with status {}",
+            existing.id, status
+        )),
+    }
+}
+
+fn db_identifier_tag(identifier: &str) -> Tag {
+    Tag::builder()
+        .key("rds-snapshot-share:db-identifier")
+        .value(identifier)
+        .build()
+}
+
+fn access_denied(action: &str, resource: &str) -> ShareError {
+    ShareError(format!(
+        "Access denied changing the sharing of {}; the current principal needs the {} IAM permission",
+        resource, action
+    ))
+}
+
+async fn verify_accounts(sts: &STS, account_ids: &[String], role_name: &str) -> bool {
+    let mut verified = true;
+
+    for account_id in account_ids {
+        let role_arn = format!("arn:aws:iam::{}:role/{}", account_id, role_name);
+
+        match sts.verify_assume_role(role_arn.clone()).await {
+            Ok(()) => eprintln!("{}: reachable", account_id),
+            Err(err) => {
+                eprintln!(
+                    "{}: could not assume {} ({}); the account may not exist in the organization",
+                    account_id, role_arn, err
+                );
+                verified = false;
+            }
+        }
+    }
+
+    verified
+}
+
+async fn wait_for_copy_slot(
+    rds: &RDS,
+    db_type: &DatabaseType,
+    max_concurrent_copies: usize,
+    wait: bool,
+) -> Result<(), ShareError> {
+    loop {
+        let in_progress = rds.count_in_progress_copies(db_type).await?;
+
+        if in_progress < max_concurrent_copies {
+            return Ok(());
+        }
+
+        if !wait {
+            return Err(ShareError(format!(
+                "{} snapshot copies are already in progress, the limit is {}; retry later or pass --wait",
+                in_progress, max_concurrent_copies
+            )));
+        }
+
+        eprintln!(
+            "{} of {} concurrent snapshot copies in progress; waiting",
+            in_progress, max_concurrent_copies
+        );
+
+        sleep(Duration::from_secs(30)).await;
+    }
+}
+
+async fn check_snapshot_ownership(
+    rds: &RDS,
+    db_type: &DatabaseType,
+    snapshot_id: &str,
+    allow_shared_source: bool,
+) -> Result<(), ShareError> {
+    if rds
+        .find_snapshot(db_type, snapshot_id.to_string())
+        .await?
+        .is_some()
+    {
+        eprintln!("{}: Owned by this account", snapshot_id);
+        return Ok(());
+    }
+
+    if let Some(shared) = rds
+        .describe_shared_snapshot(db_type, snapshot_id.to_string())
+        .await?
+    {
+        let owner = shared.arn.split(':').nth(4).unwrap_or_default();
+
+        eprintln!("{}: Shared by account {}", snapshot_id, owner);
+
+        if !allow_shared_source {
+            return Err(ShareError(
+                "Refusing to copy or share a snapshot owned by another account; pass --allow-shared-source to continue"
+                    .to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn warn_key_origin(key: &KeyMetadata) {
+    let key_id = key.key_id().unwrap_or_default();
+
+    match key.origin() {
+        Some(OriginType::External) => {
+            let expires = key
+                .valid_to()
+                .and_then(|valid_to| DateTime::from_timestamp(valid_to.secs(), 0))
+                .map(|valid_to| format!(", and expires {}", valid_to.format("%Y-%m-%d %H:%M:%S")))
+                .unwrap_or_default();
+
+            eprintln!(
+                "Warning: {} uses imported key material; it must stay imported and unexpired for the snapshot to be restorable{}",
+                key_id, expires
+            );
+        }
+        Some(OriginType::AwsCloudhsm) => eprintln!(
+            "Note: {} is backed by the CloudHSM custom key store {}",
+            key_id,
+            key.custom_key_store_id().unwrap_or_default()
+        ),
+        _ => (),
+    }
+}
+
+fn normalize_identifier_case(snapshot_id: &mut String) {
+    let lowercase = snapshot_id.to_lowercase();
+
+    if *snapshot_id != lowercase {
+        eprintln!(
+            "Note: using {} for {}; RDS snapshot identifiers are not case sensitive",
+            lowercase, snapshot_id
+        );
+        *snapshot_id = lowercase;
+    }
+}
+
+fn parse_https_url(url: &str) -> Result<String, String> {
+    let host = url
+        .strip_prefix("https://")
+        .ok_or("the endpoint must be an https:// URL")?
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default();
+
+    match !host.is_empty() && !url.contains(char::is_whitespace) {
+        true => Ok(url.trim_end_matches('/').to_string()),
+        false => Err("the endpoint URL has no host".to_string()),
+    }
+}
+
+/// A VPC endpoint serves a single region, so an endpoint override only applies to clients in
+/// the configured region; clients for other regions use their regional default.
+fn regional_endpoint_url<'a>(
+    config: &SdkConfig,
+    region: &str,
+    endpoint_url: Option<&'a str>,
+) -> Option<&'a str> {
+    endpoint_url.filter(|_| config.region().map(|configured| configured.as_ref()) == Some(region))
+}
+
+fn validate_export_task_id(export_task_id: &str) -> Result<(), String> {
+    let valid = (1..=60).contains(&export_task_id.len())
+        && export_task_id.starts_with(|c: char| c.is_ascii_alphabetic())
+        && !export_task_id.ends_with('-')
+        && !export_task_id.contains("--")
+        && export_task_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-');
+
+    match valid {
+        true => Ok(()),
+        false => Err(format!(
+            "{} is not a valid export task identifier; it must be 1 to 60 letters, digits or single hyphens, start with a letter and not end with a hyphen",
+            export_task_id
+        )),
+    }
+}
+
+fn parse_s3_uri(uri: &str) -> Option<(String, Option<String>)> {
+    let (bucket, prefix) = match uri.strip_prefix("s3://")?.split_once('/') {
+        Some((bucket, prefix)) => (bucket, Some(prefix).filter(|prefix| !prefix.is_empty())),
+        None => (uri.strip_prefix("s3://")?, None),
+    };
+
+    let valid = (3..=63).contains(&bucket.len())
+        && bucket
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.');
+
+    valid.then(|| (bucket.to_string(), prefix.map(String::from)))
+}
+
+/// The longest snapshot identifier the RDS API accepts.
+const RDS_MAX_IDENTIFIER_LENGTH: usize = 255;
+
+fn validate_snapshot_id(snapshot_id: &str) -> Result<(), String> {
+    let name = snapshot_id.rsplit(':').next().unwrap();
+
+    // RDS identifiers are ASCII, and the length checks and truncation below count bytes
+    if !name.is_ascii() {
+        return Err(format!(
+            "Snapshot identifier {} contains non-ASCII characters; RDS allows only letters, digits and hyphens",
+            name
+        ));
+    }
+
+    match name.len() > RDS_MAX_IDENTIFIER_LENGTH {
+        true => Err(format!(
+            "Snapshot identifier {} is {} characters; RDS allows at most {}",
+            name,
+            name.len(),
+            RDS_MAX_IDENTIFIER_LENGTH
+        )),
+        false => Ok(()),
+    }
+}
+
+fn check_identifier_length(snapshot_id: &str, max: usize) -> Result<(), ShareError> {
+    let name = snapshot_id.rsplit(':').next().unwrap();
+
+    validate_snapshot_id(snapshot_id).map_err(ShareError)?;
+
+    if name.len() > max {
+        return Err(ShareError(format!(
+            "Snapshot identifier {} is {} characters, longer than --max-identifier-length {}",
+            name,
+            name.len(),
+            max
+        )));
+    }
+
+    Ok(())
+}
+
+fn default_copy_snapshot_id(
+    snapshot_id: &str,
+    pitr_timestamp: Option<DateTime<Utc>>,
+    max_length: usize,
+) -> String {
+    let name = snapshot_id.rsplit(':').next().unwrap();
+    let copied = Utc::now().format("%Y%m%d%H%M%S");
+
+    let suffix = match pitr_timestamp {
+        Some(pitr) => format!("-pitr-{}-copy-{}", pitr.format("%Y%m%d%H%M%S"), copied),
+        None => format!("-copy-{}", copied),
+    };
+
+    let keep = max_length
+        .min(RDS_MAX_IDENTIFIER_LENGTH)
+        .saturating_sub(suffix.len());
+
+    if name.len() > keep {
+        eprintln!(
+            "Note: truncating {} to {} characters for the generated copy identifier",
+            name, keep
+        );
+    }
+
+    format!(
+        "{}{}",
+        &name[..name.len().min(keep)].trim_end_matches('-'),
+        suffix
+    )
+}
+
+fn generate_copy_snapshot_id(
+    snapshot_id: &str,
+    pitr_timestamp: Option<DateTime<Utc>>,
+    max_length: usize,
+    force_lowercase: bool,
+) -> String {
+    let mut generated = default_copy_snapshot_id(snapshot_id, pitr_timestamp, max_length);
+
+    if force_lowercase {
+        normalize_identifier_case(&mut generated);
+    }
+
+    generated
+}
+
+async fn copy_received_snapshot(
+    rds: &RDS,
+    args: CopyReceivedSnapshotArgs,
+    audit: &mut Option<AuditTrail>,
+) -> Result<(), rds::Error> {
+    let snapshot = match args.snapshot_arn.parse::<SnapshotArn>() {
+        Ok(snapshot) => snapshot,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    let new_snapshot_id =
+        match check_copy_collision(rds, &snapshot.db_type, args.new_snapshot_id).await {
+            Ok(CopyTarget::New(new_snapshot_id)) => new_snapshot_id,
+            Ok(CopyTarget::Existing(existing_id)) => {
+                println!("Using existing snapshot {}", existing_id);
+
+                return Ok(());
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        };
+
+    if let Some(audit) = audit.as_mut() {
+        audit.copy_snapshot(
+            &snapshot.db_type,
+            &args.snapshot_arn,
+            &new_snapshot_id,
+            args.kms_key_id.as_deref(),
+        );
+
+        return Ok(());
+    }
+
+    let copy = rds
+        .copy_snapshot(
+            &snapshot.db_type,
+            args.snapshot_arn,
+            new_snapshot_id,
+            args.kms_key_id,
+            vec![],
+        )
+        .await?;
+
+    println!("{} {}", copy.id, copy.arn);
+
+    if args.wait {
+        let copy = wait_for_snapshot(rds, &snapshot.db_type, &copy.id, None, None).await?;
+
+        if copy.status == "failed" {
+            eprintln!("Copy {} failed", copy.id);
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_restore_targets(options: &[OrderableDbInstanceOption]) -> Vec<String> {
+    let mut targets: BTreeMap<String, (BTreeSet<String>, bool)> = BTreeMap::new();
+
+    for option in options {
+        let (storage_types, multi_az) = targets
+            .entry(option.db_instance_class().unwrap_or_default().to_string())
+            .or_default();
+
+        if let Some(storage_type) = option.storage_type() {
+            storage_types.insert(storage_type.to_string());
+        }
+        *multi_az |= option.multi_az_capable();
+    }
+
+    let rows: Vec<_> = targets
+        .iter()
+        .map(|(class, (storage_types, multi_az))| {
+            vec![
+                class.clone(),
+                storage_types.iter().cloned().collect::<Vec<_>>().join(","),
+                multi_az.to_string(),
+            ]
+        })
+        .collect();
+
+    print_table(&["Instance Class", "Storage Types", "Multi-AZ"], &rows);
+
+    targets.into_keys().collect()
+}
+
+async fn restore_local(
+    rds: &RDS,
+    args: RestoreLocalArgs,
+    audit: &mut Option<AuditTrail>,
+) -> Result<(), rds::Error> {
+    if args.describe_restore_targets {
+        let snapshot = rds
+            .describe_snapshot(&args.db_type, args.source_snapshot_id.clone())
+            .await?;
+        let options = rds
+            .describe_orderable_db_instance_options(snapshot.engine, snapshot.engine_version)
+            .await?;
+
+        let classes = print_restore_targets(&options);
+
+        if let Some(instance_class) = &args.instance_class {
+            if !classes.contains(instance_class) {
+                eprintln!(
+                    "{} is not an orderable instance class for {}",
+                    instance_class, args.source_snapshot_id
+                );
+                process::exit(1);
+            }
+        }
+    }
+
+    match args.db_type {
+        DatabaseType::Cluster => {
+            let snapshot = rds
+                .describe_db_cluster_snapshot(args.source_snapshot_id.clone())
+                .await?;
+            let engine = snapshot.engine().unwrap_or_default().to_string();
+            let availability_zones = snapshot.availability_zones().unwrap_or_default();
+            let instance_identifier = format!("{}-1", args.new_db_identifier);
+
+            if args.detect_aurora_version && engine == "aurora-mysql" {
+                warn_aurora_version(
+                    snapshot.engine_version().unwrap_or_default(),
+                    args.engine_version.as_deref(),
+                );
+            }
+
+            if let Some(subnet_group) = &args.subnet_group {
+                let covered = rds
+                    .describe_subnet_group_availability_zones(subnet_group.clone())
+                    .await?;
+
+                let missing: Vec<_> = availability_zones
+                    .iter()
+                    .filter(|az| !covered.contains(az))
+                    .cloned()
+                    .collect();
+
+                if !missing.is_empty() {
+                    eprintln!(
+                        "Subnet group {} does not cover availability zones required by the snapshot: {}",
+                        subnet_group,
+                        missing.join(", ")
+                    );
+                    process::exit(1);
+                }
+            }
+
+            if let Some(audit) = audit.as_mut() {
+                audit.describe(
+                    "DescribeDBClusterSnapshots",
+                    json!({ "DBClusterSnapshotIdentifier": args.source_snapshot_id }),
+                    json!({ "Engine": engine, "AvailabilityZones": availability_zones }),
+                );
+                audit.mutate(
+                    "RestoreDBClusterFromSnapshot",
+                    json!({
+                        "SnapshotIdentifier": args.source_snapshot_id,
+                        "DBClusterIdentifier": args.new_db_identifier,
+                        "Engine": engine,
+                        "EngineVersion": args.engine_version,
+                        "DBSubnetGroupName": args.subnet_group,
+                        "VpcSecurityGroupIds": args.security_groups,
+                    }),
+                );
+
+                if let Some(instance_class) = args.instance_class {
+                    audit.mutate(
+                        "CreateDBInstance",
+                        json!({
+                            "DBClusterIdentifier": args.new_db_identifier,
+                            "DBInstanceIdentifier": instance_identifier,
+                            "Engine": engine,
+                            "DBInstanceClass": instance_class,
+                        }),
+                    );
+                }
+
+                return Ok(());
+            }
+
+            rds.restore_db_cluster_from_snapshot(
+                args.source_snapshot_id,
+                args.new_db_identifier.clone(),
+                engine.clone(),
+                args.engine_version,
+                args.subnet_group,
+                args.security_groups,
+            )
+            .await?;
+
+            println!("Restoring cluster {}", args.new_db_identifier);
+
+            let creates_instance = args.instance_class.is_some();
+
+            if let Some(instance_class) = args.instance_class {
+                rds.create_cluster_instance(
+                    args.new_db_identifier.clone(),
+                    instance_identifier.clone(),
+                    engine,
+                    instance_class,
+                )
+                .await?;
+
+                println!("Creating instance {}", instance_identifier);
+            }
+
+            if args.wait {
+                wait_until_available(rds, &args.db_type, &args.new_db_identifier).await?;
+
+                if creates_instance {
+                    wait_until_available(rds, &DatabaseType::Database, &instance_identifier)
+                        .await?;
+                }
+            }
+        }
+        DatabaseType::Database => {
+            if let Some(audit) = audit.as_mut() {
+                audit.mutate(
+                    "RestoreDBInstanceFromDBSnapshot",
+                    json!({
+                        "DBSnapshotIdentifier": args.source_snapshot_id,
+                        "DBInstanceIdentifier": args.new_db_identifier,
+                        "DBInstanceClass": args.instance_class,
+                        "DBSubnetGroupName": args.subnet_group,
+                        "VpcSecurityGroupIds": args.security_groups,
+                    }),
+                );
+
+                return Ok(());
+            }
+
+            rds.restore_db_instance_from_db_snapshot(
+                args.source_snapshot_id,
+                args.new_db_identifier.clone(),
+                args.instance_class,
+                args.subnet_group,
+                args.security_groups,
+            )
+            .await?;
+
+            println!("Restoring instance {}", args.new_db_identifier);
+
+            if args.wait {
+                wait_until_available(rds, &args.db_type, &args.new_db_identifier).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), rds::Error> {
+    let started = Instant::now();
+    let mut args = Args::parse();
+    let mut audit = args.audit_mode.then(AuditTrail::default);
+
+    if args.snapshot_management_api_version_check {
+        check_sdk_version();
+    }
+
+    for snapshot_id in args.command.iter_mut().flat_map(Command::snapshot_ids_mut) {
+        if let Err(err) = validate_snapshot_id(snapshot_id) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+
+        if !args.no_force_snapshot_identifier_case {
+            normalize_identifier_case(snapshot_id);
+        }
+    }
+
+    let config = aws_config::load_from_env().await;
+    let sts = STS::new(&config);
+
+    if args.display_caller_identity {
+        let caller = sts.get_caller_identity().await.unwrap();
+
+        eprintln!(
+            "Account: {} | ARN: {} | User ID: {}",
+            caller.account().unwrap_or_default(),
+            caller.arn().unwrap_or_default(),
+            caller.user_id().unwrap_or_default()
+        );
+    }
+
+    if args.snapshot_arns_from_stdin {
+        let account_ids =
+            limit_accounts(args.account_ids.unwrap_or_default(), args.max_share_per_run);
+
+        if account_ids.is_empty() {
+            eprintln!("At least one account id is required to share snapshots");
+            process::exit(1);
+        }
+
+        let all_shared = share_snapshot_arns(
+            read_snapshot_arns(),
+            account_ids,
+            &config,
+            args.rds_endpoint_url.as_deref(),
+            &mut audit,
+        )
+        .await;
+
+        if let Some(audit) = audit {
+            audit.print();
+        }
+
+        if !all_shared {
+            process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    let rds = RDS::with_endpoint(&config, args.rds_endpoint_url.as_deref());
+    let kms = KMS::with_endpoint(&config, args.kms_endpoint_url.as_deref());
+
+    if args.check_kms_key_in_use {
+        let kms_key_id = args.kms_key_id.unwrap();
+        let key = kms.describe_key(kms_key_id.clone()).await.unwrap();
+
+        let snapshots = rds
+            .describe_snapshots_by_kms_key(key.arn().unwrap())
+            .await?;
+
+        println!("{} is used by {} snapshot(s)", kms_key_id, snapshots.len());
+
+        for snapshot in &snapshots {
+            println!("{}", snapshot);
+        }
+
+        if !snapshots.is_empty() {
+            process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    match args.command {
+        Some(Command::RestoreLocal(restore_args)) => {
+            restore_local(&rds, restore_args, &mut audit).await?;
+
+            if let Some(audit) = audit {
+                audit.print();
+            }
+
+            return Ok(());
+        }
+        Some(Command::CopyProgress(progress_args)) => {
+            let snapshot = wait_for_snapshot(
+                &rds,
+                &progress_args.db_type,
+                &progress_args.snapshot_id,
+                progress_args.progress_file.as_deref(),
+                None,
+            )
+            .await?;
+
+            if snapshot.status == "failed" {
+                process::exit(1);
+            }
+
+            return Ok(());
+        }
+        Some(Command::CopyReceivedSnapshot(copy_args)) => {
+            copy_received_snapshot(&rds, copy_args, &mut audit).await?;
+
+            if let Some(audit) = audit {
+                audit.print();
+            }
+
+            return Ok(());
+        }
+        Some(Command::CompareAttributes(compare_args)) => {
+            let (a, b) = join!(
+                rds.describe_restore_accounts(&compare_args.db_type, compare_args.snapshot_a),
+                rds.describe_restore_accounts(&compare_args.db_type, compare_args.snapshot_b),
+            );
+
+            print_attribute_comparison(&a?, &b?);
+
+            return Ok(());
+        }
+        Some(Command::ListSnapshots(list_args)) => {
+            list_snapshots(&rds, list_args).await?;
+
+            return Ok(());
+        }
+        Some(Command::ListAllSnapshots(list_args)) => {
+            list_all_snapshots(&rds, &list_args.format).await?;
+
+            return Ok(());
+        }
+        Some(Command::SimulateRestore(simulate_args)) => {
+            if !simulate_restore(
+                &config,
+                simulate_args,
+                args.rds_endpoint_url.as_deref(),
+                args.kms_endpoint_url.as_deref(),
+            )
+            .await
+            {
+                process::exit(1);
+            }
+
+            return Ok(());
+        }
+        Some(Command::Inventory(list_args)) => {
+            inventory(&config, &list_args.format, args.rds_endpoint_url.as_deref()).await?;
+
+            return Ok(());
+        }
+        Some(Command::SnapshotAttributeHistory(history_args)) => {
+            let rows: Vec<_> = CloudTrail::new(&config)
+                .lookup_attribute_changes(&history_args.db_type, &history_args.snapshot_id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|change| {
+                    vec![
+                        change.time,
+                        change.principal,
+                        change.added.join(","),
+                        change.removed.join(","),
+                    ]
+                })
+                .collect();
+
+            print_table(&["Time", "Changed By", "Added", "Removed"], &rows);
+
+            return Ok(());
+        }
+        Some(Command::HealthCheck) => {
+            let (rds_health, kms_health) = join!(rds.ping(), kms.ping());
+            let mut healthy = true;
+
+            if let Err(err) = rds_health {
+                eprintln!("RDS API check failed: {}", err);
+                healthy = false;
+            }
+
+            if let Err(err) = kms_health {
+                eprintln!("KMS API check failed: {}", err);
+                healthy = false;
+            }
+
+            if !healthy {
+                process::exit(1);
+            }
+
+            println!("healthy");
+
+            return Ok(());
+        }
+        Some(Command::DescribeProxies) => {
+            let rows: Vec<_> = rds
+                .describe_db_proxies()
+                .await?
+                .into_iter()
+                .map(|proxy| {
+                    vec![
+                        proxy.name,
+                        proxy.engine_family,
+                        proxy.status,
+                        proxy.endpoint,
+                        proxy.target_groups.join(","),
+                        proxy.targets.join(","),
+                    ]
+                })
+                .collect();
+
+            print_table(
+                &[
+                    "Name",
+                    "Engine",
+                    "Status",
+                    "Endpoint",
+                    "Target Groups",
+                    "Targets",
+                ],
+                &rows,
+            );
+
+            return Ok(());
+        }
+        Some(Command::ListGrants(grants_args)) => {
+            let grants = kms.list_grants(&grants_args.key_id).await.unwrap();
+
+            let rows: Vec<_> = grants
+                .iter()
+                .filter(|grant| match &grants_args.filter_grantee {
+                    Some(pattern) => grant
+                        .grantee_principal()
+                        .unwrap_or_default()
+                        .contains(pattern.as_str()),
+                    None => true,
+                })
+                .map(|grant| {
+                    vec![
+                        grant.grant_id().unwrap_or_default().to_string(),
+                        grant.grantee_principal().unwrap_or_default().to_string(),
+                        grant
+                            .operations()
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|operation| operation.as_str())
+                            .collect::<Vec<_>>()
+                            .join(","),
+                        grant
+                            .creation_date()
+                            .and_then(|date| DateTime::from_timestamp(date.secs(), 0))
+                            .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+                            .unwrap_or_default(),
+                    ]
+                })
+                .collect();
+
+            print_table(&["Grant ID", "Grantee", "Operations", "Created"], &rows);
+
+            return Ok(());
+        }
+        Some(Command::KmsKeyUsage) => {
+            print_kms_key_usage(&rds, &kms).await?;
+
+            return Ok(());
+        }
+        Some(Command::BatchYaml(batch_args)) => {
+            if audit.is_some() {
+                eprintln!("--audit-mode is not supported by batch-yaml");
+                process::exit(1);
+            }
+
+            if !batch_yaml(
+                &config,
+                batch_args,
+                args.rds_endpoint_url.as_deref(),
+                args.kms_endpoint_url.as_deref(),
+            )
+            .await
+            {
+                process::exit(1);
+            }
+
+            return Ok(());
+        }
+        Some(Command::WatchAll(watch_args)) => {
+            if !watch_all(&rds, watch_args).await? {
+                process::exit(1);
+            }
+
+            return Ok(());
+        }
+        Some(Command::CopyAttributes(copy_args)) => {
+            if let Err(err) = copy_snapshot_attributes(&rds, copy_args, &mut audit).await {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+
+            if let Some(audit) = audit {
+                audit.print();
+            }
+
+            return Ok(());
+        }
+        None => (),
+    }
+
+    if !args.no_force_snapshot_identifier_case {
+        for snapshot_id in [&mut args.snapshot_id, &mut args.new_snapshot_id]
+            .into_iter()
+            .flatten()
+        {
+            normalize_identifier_case(snapshot_id);
+        }
+    }
+
+    #[cfg(feature = "pagerduty")]
+    let pagerduty = args
+        .pagerduty_integration_key
+        .clone()
+        .map(|key| (PagerDuty::new(key), args.pagerduty_resolve));
+    #[cfg(feature = "pagerduty")]
+    let subject = args
+        .snapshot_id
+        .clone()
+        .or_else(|| args.db_identifier.clone())
+        .unwrap_or_else(|| "interactive session".to_string());
+
+    let result = share(args, &config, &rds, &kms, &sts, audit, started).await;
+
+    if let Err(err) = &result {
+        eprintln!("{}", err);
+    }
+
+    #[cfg(feature = "pagerduty")]
+    if let Some((pagerduty, resolve)) = pagerduty {
+        let sent = match &result {
+            Err(err) => pagerduty.trigger(&subject, &err.to_string()).await,
+            Ok(()) if resolve => pagerduty.resolve(&subject).await,
+            Ok(()) => Ok(()),
+        };
+
+        if let Err(err) = sent {
+            eprintln!("Warning: could not notify PagerDuty: {}", err);
+        }
+    }
+
+    if result.is_err() {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+async fn share(
+    mut args: Args,
+    config: &SdkConfig,
+    rds: &RDS,
+    kms: &KMS,
+    sts: &STS,
+    mut audit: Option<AuditTrail>,
+    started: Instant,
+) -> Result<(), ShareError> {
+    for snapshot_id in [&args.snapshot_id, &args.new_snapshot_id]
+        .into_iter()
+        .flatten()
+    {
+        check_identifier_length(snapshot_id, args.max_identifier_length)?;
+    }
+
+    // The generated copy name is known as soon as --snapshot-id is, so it is checked here too
+    let generated_copy_id = match (&args.snapshot_id, &args.new_snapshot_id) {
+        (Some(snapshot_id), None) => {
+            let generated = generate_copy_snapshot_id(
+                snapshot_id,
+                args.pitr_timestamp,
+                args.max_identifier_length,
+                !args.no_force_snapshot_identifier_case,
+            );
+
+            check_identifier_length(&generated, args.max_identifier_length)?;
+
+            Some(generated)
+        }
+        _ => None,
+    };
+
+    let export_destination = match &args.copy_to_account_s3 {
+        Some(uri) => {
+            let Some((bucket, prefix)) = parse_s3_uri(uri) else {
+                return Err(ShareError(format!(
+                    "{} is not a valid S3 URI; expected s3://bucket[/prefix]",
+                    uri
+                )));
+            };
+
+            let export_task_id = args.export_task_id.clone().unwrap_or_else(|| {
+                format!(
+                    "rds-snapshot-share-export-{}",
+                    Utc::now().format("%Y%m%d%H%M%S")
+                )
+            });
+
+            validate_export_task_id(&export_task_id).map_err(ShareError)?;
+
+            let role_arn = args.iam_role_arn.clone().ok_or_else(|| {
+                ShareError("--copy-to-account-s3 requires --iam-role-arn".to_string())
+            })?;
+            let object_arn = format!(
+                "arn:aws:s3:::{}/{}*",
+                bucket,
+                prefix.as_deref().unwrap_or_default()
+            );
+
+            if !IAM::new(config)
+                .can_put_object(&role_arn, &object_arn)
+                .await?
+            {
+                return Err(ShareError(format!(
+                    "{} is not allowed s3:PutObject on {}; the export would fail",
+                    role_arn, object_arn
+                )));
+            }
+
+            Some((export_task_id, bucket, prefix, role_arn))
+        }
+        None => None,
+    };
+
+    if args.db_cluster_resource_id.is_some() {
+        args.db_type = DatabaseType::Cluster;
+    }
+
+    if args.dbi_resource_id.is_some() {
+        args.db_type = DatabaseType::Database;
+    }
+
+    let identifier = match (
+        args.db_identifier,
+        args.db_cluster_resource_id,
+        args.dbi_resource_id,
+    ) {
+        (Some(id), _, _) => id,
+        (None, Some(resource_id), _) => {
+            let filter = Filter::builder()
+                .name("db-cluster-resource-id")
+                .values(resource_id.clone())
+                .build();
+
+            match rds.describe_clusters(vec![filter]).await?.pop() {
+                Some(id) => id,
+                None => {
+                    return Err(ShareError(format!(
+                        "No cluster has resource id {}",
+                        resource_id
+                    )));
+                }
+            }
+        }
+        (None, None, Some(resource_id)) => {
+            let filter = Filter::builder()
+                .name("dbi-resource-id")
+                .values(resource_id.clone())
+                .build();
+
+            match rds.describe_instances(vec![filter]).await?.pop() {
+                Some(id) => id,
+                None => {
+                    return Err(ShareError(format!(
+                        "No instance has resource id {}",
+                        resource_id
+                    )));
+                }
+            }
+        }
+        (None, None, None) => {
+            let (operation, identifiers) = match args.db_type {
+                DatabaseType::Database => {
+                    ("DescribeDBInstances", rds.describe_instances(vec![]).await?)
+                }
+                DatabaseType::Cluster => {
+                    ("DescribeDBClusters", rds.describe_clusters(vec![]).await?)
+                }
+            };
+
+            if let Some(audit) = audit.as_mut() {
+                audit.describe(operation, json!({}), json!(identifiers));
+            }
+
+            if !args.no_auto_select && identifiers.len() == 1 {
+                eprintln!("Auto-selected only available database: {}", identifiers[0]);
+
+                identifiers[0].clone()
+            } else {
+                let mut refreshing = args.instance_list_refresh_interval.map(|interval| {
+                    let rds = rds.clone();
+                    let db_type = args.db_type.clone();
+
+                    refresh_in_background(interval, identifiers.clone(), move || {
+                        let rds = rds.clone();
+                        let db_type = db_type.clone();
+
+                        async move { rds.describe_databases(&db_type).await.ok() }
+                    })
+                });
+
+                let vpc_names = match args.resolve_vpc_names && !args.no_detail_panel {
+                    true => match EC2::new(config).describe_vpc_names().await {
+                        Ok(names) => names,
+                        Err(err) => {
+                            eprintln!(
+                                "Warning: could not describe VPCs, showing IDs only: {}",
+                                err
+                            );
+                            HashMap::new()
+                        }
+                    },
+                    false => HashMap::new(),
+                };
+
+                loop {
+                    let identifier = match refreshing.as_mut() {
+                        Some(identifiers) => select_rds_refreshing(identifiers),
+                        None => select_rds(identifiers.clone()),
+                    }?;
+
+                    if args.no_detail_panel {
+                        break identifier;
+                    }
+
+                    let mut details = rds
+                        .describe_database_details(&args.db_type, identifier.clone())
+                        .await?;
+
+                    // Clusters only name their subnet group, which is what carries the VPC
+                    if args.resolve_vpc_names && details.vpc_id.is_none() {
+                        if let Some(subnet_group) = details.subnet_group.clone() {
+                            details.vpc_id = rds.describe_subnet_group_vpc(subnet_group).await?;
+                        }
+                    }
+
+                    details.vpc_name = details
+                        .vpc_id
+                        .as_ref()
+                        .and_then(|vpc_id| vpc_names.get(vpc_id).cloned());
+
+                    eprintln!("{}", details);
+
+                    if confirm_database(&identifier)? {
+                        break identifier;
+                    }
+                }
+            }
+        }
+    };
+
+    let kms_key_id = match args.kms_key_id {
+        Some(kms_key_id) if kms_key_id.starts_with("arn:") => {
+            let key_account = kms_key_id.split(':').nth(4).unwrap_or_default();
+            let caller = sts.get_caller_identity().await?;
+            let account = caller.account().unwrap_or_default();
+
+            if key_account != account && !args.allow_cross_account_kms {
+                return Err(ShareError(format!(
+                    "KMS key {} belongs to account {}, not {}. Copying and sharing with another account's key requires the RDS service role to have access to it; pass --allow-cross-account-kms to continue",
+                    kms_key_id, key_account, account
+                )));
+            }
+
+            Ok(kms_key_id)
+        }
+        Some(kms_key_id) => Ok(kms_key_id),
+        None => {
+            let mut keys = kms.list_keys(&args.key_manager).await?;
+
+            if args.key_description_display {
+                keys = kms.describe_keys(keys).await;
+            }
+
+            if let Some(audit) = audit.as_mut() {
+                let ids: Vec<_> = keys.iter().map(|key| key.id.clone()).collect();
+
+                audit.describe("ListKeys", json!({}), json!(ids));
+            }
+
+            match args.kms_key_refresh_interval {
+                Some(interval) => {
+                    let kms = kms.clone();
+                    let key_manager = args.key_manager.clone();
+                    let with_descriptions = args.key_description_display;
+
+                    let mut keys = refresh_in_background(interval, keys, move || {
+                        let kms = kms.clone();
+                        let key_manager = key_manager.clone();
+
+                        async move {
+                            let keys = kms.list_keys(&key_manager).await.ok()?;
+
+                            match with_descriptions {
+                                true => Some(kms.describe_keys(keys).await),
+                                false => Some(keys),
+                            }
+                        }
+                    });
+
+                    select_keys_refreshing(&mut keys)
+                }
+                None => select_keys(keys),
+            }
+        }
+    }?;
+
+    if args.check_automated_backup_settings || args.check_db_upgrade_in_progress {
+        let details = rds
+            .describe_database_details(&args.db_type, identifier.clone())
+            .await?;
+
+        if args.check_automated_backup_settings {
+            warn_backup_settings(&identifier, &details);
+        }
+
+        if args.check_db_upgrade_in_progress && !confirm_upgrade_in_progress(&identifier, &details)?
+        {
+            return Err(ShareError(format!(
+                "Not sharing {}: cancelled while its upgrade is in progress",
+                identifier
+            )));
+        }
+    }
+
+    if args.show_recent_events {
+        let source_type = match args.db_type {
+            DatabaseType::Cluster => "db-cluster",
+            DatabaseType::Database => "db-instance",
+        };
+
+        let events = rds
+            .describe_events(
+                identifier.clone(),
+                source_type.to_string(),
+                args.events_duration_hours,
+            )
+            .await?;
+
+        eprintln!(
+            "{} event(s) for {} in the last {} hours",
+            events.len(),
+            identifier,
+            args.events_duration_hours
+        );
+
+        for event in &events {
+            eprintln!(
+                "  {} [{}] {}",
+                event.date,
+                event.categories.join(","),
+                event.message
+            );
+        }
+    }
+
+    if let Some(minutes) = args.check_recently_modified {
+        if !confirm_recently_modified(config, &args.db_type, &identifier, minutes).await? {
+            return Err(ShareError(format!(
+                "Not sharing {}: cancelled after its recent modifications",
+                identifier
+            )));
+        }
+    }
+
+    let filter = SnapshotFilter {
+        backup_target: args.backup_target.clone(),
+        max_snapshots: args.max_snapshots.map(|max| max as usize),
+    };
+
+    let snapshot_provided = args.snapshot_id.is_some();
+
+    let snapshot = match args.snapshot_id {
+        Some(snap) => snap,
+        None => {
+            let snapshots = rds
+                .describe_snapshots(&args.db_type, identifier.clone(), &filter)
+                .await?;
+
+            if let Some(audit) = audit.as_mut() {
+                let operation = match args.db_type {
+                    DatabaseType::Cluster => "DescribeDBClusterSnapshots",
+                    DatabaseType::Database => "DescribeDBSnapshots",
+                };
+
+                audit.describe(
+                    operation,
+                    json!({ "Identifier": identifier }),
+                    json!(snapshots),
+                );
+            }
+
+            match args.snapshot_list_refresh_interval {
+                _ if !args.no_auto_select && snapshots.len() == 1 => {
+                    eprintln!(
+                        "Auto-selected only available snapshot: {}",
+                        snapshots[0].split('|').next().unwrap()
+                    );
+
+                    snapshots[0].clone()
+                }
+                Some(interval) => {
+                    let rds = rds.clone();
+                    let db_type = args.db_type.clone();
+                    let identifier = identifier.clone();
+                    let filter = filter.clone();
+
+                    let mut snapshots = refresh_in_background(interval, snapshots, move || {
+                        let rds = rds.clone();
+                        let db_type = db_type.clone();
+                        let identifier = identifier.clone();
+                        let filter = filter.clone();
+
+                        async move {
+                            rds.describe_snapshots(&db_type, identifier, &filter)
+                                .await
+                                .ok()
+                        }
+                    });
+
+                    select_snapshot_refreshing(&mut snapshots)?
+                }
+                None => select_snapshot(snapshots)?,
+            }
+        }
+    };
+
+    let snapshot_id = snapshot.split('|').next().unwrap().to_string();
+
+    if snapshot_provided {
+        check_snapshot_ownership(rds, &args.db_type, &snapshot_id, args.allow_shared_source)
+            .await?;
+    }
+    let mut account_ids = match args.account_ids {
+        Some(account_ids) if !account_ids.is_empty() => account_ids,
+        _ if args.interactive_account_input => prompt_account_ids()?,
+        _ => vec![],
+    };
+
+    if args.share_with_self {
+        let caller = sts.get_caller_identity().await?;
+        let account = caller
+            .account()
+            .ok_or_else(|| ShareError("The caller identity has no account ID".to_string()))?
+            .to_string();
+
+        if !account_ids.contains(&account) {
+            account_ids.push(account);
+        }
+    }
+
+    if let Some(OrgAccountStatus::Active) = args.org_account_filter_status {
+        let inactive = Organizations::new(config).list_inactive_accounts().await?;
+
+        account_ids.retain(|account_id| match inactive.get(account_id) {
+            Some(status) => {
+                eprintln!(
+                    "Not sharing with {}: the organization reports it as {}",
+                    account_id, status
+                );
+                false
+            }
+            None => true,
+        });
+    }
+
+    // Limit after filtering, so the run shares with exactly the number it reports
+    let account_ids = limit_accounts(account_ids, args.max_share_per_run);
+
+    if args.verify_accounts && !verify_accounts(sts, &account_ids, &args.verify_role_name).await {
+        return Err(ShareError(
+            "Not sharing: some target accounts could not be verified".to_string(),
+        ));
+    }
+
+    let source = rds
+        .describe_snapshot(&args.db_type, snapshot_id.clone())
+        .await?;
+    let key = kms.describe_key(kms_key_id.clone()).await?;
+
+    if key.key_manager() == Some(&KeyManagerType::Aws) {
+        return Err(ShareError(format!(
+            "Not sharing {}: {} is an AWS managed key, whose policy cannot grant other accounts access. Choose a customer managed key instead",
+            snapshot_id, kms_key_id
+        )));
+    }
+
+    if args.detect_cmk_encrypted_with_aws_managed_key {
+        warn_key_origin(&key);
+    }
+
+    let kms_key_description = key
+        .description()
+        .filter(|_| args.key_description_display)
+        .map(String::from);
+
+    if args.check_lifecycle_policy {
+        warn_lifecycle_policy(&source);
+    }
+
+    if source.snapshot_target == "outposts" {
+        eprintln!(
+            "Warning: {} is stored on an AWS Outpost; Outposts snapshots cannot be copied or shared across regions",
+            source.id
+        );
+    }
+
+    let encrypted_with_customer_key = match &source.kms_key_id {
+        Some(source_key_id) if args.encrypt_and_copy_if_unencrypted => {
+            let source_key = kms.describe_key(source_key_id.clone()).await?;
+
+            source_key.key_manager() == Some(&KeyManagerType::Customer)
+        }
+        _ => false,
+    };
+
+    let copy_snapshot_id =
+        if source.kms_key_id.as_deref() == key.arn() || encrypted_with_customer_key {
+            None
+        } else {
+            Some(match (args.new_snapshot_id.take(), generated_copy_id) {
+                (Some(copy_snapshot_id), _) | (None, Some(copy_snapshot_id)) => copy_snapshot_id,
+                (None, None) => generate_copy_snapshot_id(
+                    &snapshot_id,
+                    args.pitr_timestamp,
+                    args.max_identifier_length,
+                    !args.no_force_snapshot_identifier_case,
+                ),
+            })
+        };
+
+    if let Some(copy_snapshot_id) = &copy_snapshot_id {
+        check_identifier_length(copy_snapshot_id, args.max_identifier_length)?;
+    }
+
+    let mut snapshot_id = snapshot_id;
+
+    let copy_snapshot_id = match copy_snapshot_id {
+        Some(copy_snapshot_id) => {
+            match check_copy_collision(rds, &args.db_type, copy_snapshot_id).await? {
+                CopyTarget::New(copy_snapshot_id) => Some(copy_snapshot_id),
+                CopyTarget::Existing(existing_id) => {
+                    snapshot_id = existing_id;
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    if args.check_service_quotas && copy_snapshot_id.is_some() {
+        let (used, limit) = check_snapshot_quota(config, rds, &args.db_type).await?;
+
+        if let Some(audit) = audit.as_mut() {
+            audit.describe(
+                "GetServiceQuota",
+                json!({ "ServiceCode": "rds" }),
+                json!({ "Used": used, "Value": limit }),
+            );
+        }
+
+        let percent = match limit > 0.0 {
+            true => used as f64 / limit * 100.0,
+            false => 0.0,
+        };
+
+        if percent >= 100.0 {
+            return Err(ShareError(
+                "Manual snapshot quota is exhausted; not copying the snapshot".to_string(),
+            ));
+        }
+
+        if percent > args.quota_usage_warning_threshold as f64 {
+            eprintln!(
+                "Warning: manual snapshot usage is at {:.0}% of the quota",
+                percent
+            );
+        }
+    }
+
+    if args.check_concurrent_snapshot_limit && copy_snapshot_id.is_some() {
+        match audit.as_mut() {
+            // Audit mode reports the copies in progress rather than waiting for a slot
+            Some(audit) => {
+                let in_progress = rds.count_in_progress_copies(&args.db_type).await?;
+
+                audit.describe(
+                    match args.db_type {
+                        DatabaseType::Cluster => "DescribeDBClusterSnapshots",
+                        DatabaseType::Database => "DescribeDBSnapshots",
+                    },
+                    json!({ "SnapshotType": "manual" }),
+                    json!({
+                        "CopiesInProgress": in_progress,
+                        "MaxConcurrentCopies": args.max_concurrent_copies,
+                    }),
+                );
+            }
+            None => {
+                wait_for_copy_slot(rds, &args.db_type, args.max_concurrent_copies, args.wait)
+                    .await?
+            }
+        }
+    }
+
+    // Key policy operations only accept a key ID or ARN, never an alias
+    let policy_key_arn = match encrypted_with_customer_key {
+        true => source.kms_key_id.clone().unwrap(),
+        false => key.arn().unwrap_or_default().to_string(),
+    };
+
+    if let Some(audit) = audit.as_mut() {
+        audit.describe(
+            match args.db_type {
+                DatabaseType::Cluster => "DescribeDBClusterSnapshots",
+                DatabaseType::Database => "DescribeDBSnapshots",
+            },
+            json!({ "Identifier": snapshot_id }),
+            json!({
+                "KmsKeyId": source.kms_key_id,
+                "AvailabilityZones": source.availability_zones,
+            }),
+        );
+        audit.describe(
+            "DescribeKey",
+            json!({ "KeyId": kms_key_id }),
+            json!({ "Arn": key.arn() }),
+        );
+
+        if let Some(copy_snapshot_id) = &copy_snapshot_id {
+            audit.copy_snapshot(
+                &args.db_type,
+                &snapshot_id,
+                copy_snapshot_id,
+                Some(&kms_key_id),
+            );
+        }
+
+        let waits_for_copy = copy_snapshot_id.is_none() || !args.no_wait_for_copy;
+
+        if waits_for_copy && !account_ids.is_empty() {
+            let share_id = copy_snapshot_id.as_ref().unwrap_or(&snapshot_id);
+
+            audit.share_snapshot(&args.db_type, share_id, &account_ids);
+
+            if args.snapshot_tag_accounts_count {
+                audit.tag_shared_accounts(share_id);
+            }
+        }
+
+        if let Some((export_task_id, bucket, prefix, role_arn)) = &export_destination {
+            let share_id = copy_snapshot_id.as_ref().unwrap_or(&snapshot_id);
+
+            audit.mutate(
+                "StartExportTask",
+                json!({
+                    "ExportTaskIdentifier": export_task_id,
+                    "SourceSnapshot": share_id,
+                    "S3BucketName": bucket,
+                    "S3Prefix": prefix,
+                    "IamRoleArn": role_arn,
+                    "KmsKeyId": policy_key_arn,
+                }),
+            );
+        }
+
+        if args.kms_key_policy_auto_update_for_org {
+            let org_id = Organizations::new(config)
+                .describe_organization_id()
+                .await?;
+
+            audit.describe("DescribeOrganization", json!({}), json!({ "Id": org_id }));
+            audit.mutate(
+                "PutKeyPolicy",
+                json!({
+                    "KeyId": policy_key_arn,
+                    "PolicyName": "default",
+                    "AddedStatement": org_key_policy_statement(&org_id),
+                }),
+            );
+        }
+
+        audit.print();
+
+        return Ok(());
+    }
+
+    let kms_key_id = match encrypted_with_customer_key {
+        true => source.kms_key_id.clone().unwrap(),
+        false => kms_key_id,
+    };
+
+    let text = matches!(args.output, OutputFormat::Text);
+
+    if text {
+        println!("{} {} {}", &identifier, &kms_key_id, &snapshot);
+    }
+
+    if args.describe_option_groups {
+        let groups = rds
+            .describe_option_groups(source.engine.clone(), source.option_group_name.clone())
+            .await?;
+
+        print_option_groups(&source, &groups);
+    }
+
+    if args.check_db_proxy_targets {
+        let proxies = rds.describe_db_proxies().await?;
+
+        for proxy in proxies
+            .iter()
+            .filter(|proxy| proxy.targets.contains(&identifier))
+        {
+            eprintln!(
+                "Note: {} is a target of RDS Proxy {}; the proxy endpoint {} will not reach a restored copy until it is registered as a target",
+                identifier, proxy.name, proxy.endpoint
+            );
+        }
+    }
+
+    if source.availability_zones.len() > 1 {
+        eprintln!(
+            "Snapshot contains data from availability zones {}; the restore target must have these availability zones available",
+            source.availability_zones.join(", ")
+        );
+    }
+
+    if let Some(storage_type) = &args.copy_storage_type {
+        match args.db_type {
+            DatabaseType::Cluster => eprintln!(
+                "Warning: --copy-storage-type only applies to instance snapshots; ignoring it"
+            ),
+            DatabaseType::Database => eprintln!(
+                "Storage type: {} on the source snapshot, {} on restore; the storage type affects the restored instance's performance",
+                source.storage_type.as_deref().unwrap_or("unknown"),
+                storage_type
+            ),
+        }
+
+        if let StorageType::Io1 = storage_type {
+            eprintln!("Warning: io1 storage costs significantly more than gp2 or gp3");
+        }
+    }
+
+    if args.sharing_cost_estimate {
+        let monthly = source.allocated_storage_gib as f64 * args.storage_price_per_gib;
+
+        eprintln!(
+            "Estimated storage cost: {} GiB at ${}/GiB-month = ${:.2}/month",
+            source.allocated_storage_gib, args.storage_price_per_gib, monthly
+        );
+
+        if copy_snapshot_id.is_some() {
+            eprintln!("The copy adds about ${:.2}/month", monthly);
+        }
+    }
+
+    let copied = copy_snapshot_id.is_some();
+
+    let mut copy_tags = vec![];
+
+    if !args.no_db_identifier_tag {
+        copy_tags.push(db_identifier_tag(&identifier));
+    }
+
+    if args.snapshot_creation_source_tag && copied {
+        let caller = sts.get_caller_identity().await?;
+
+        copy_tags.push(
+            Tag::builder()
+                .key("rds-snapshot-share:created-by")
+                .value(caller.arn().unwrap_or_default())
+                .build(),
+        );
+    }
+
+    if args.snapshot_tag_kms_alias && copied {
+        if let Some(alias) = kms.find_alias(key.key_id().unwrap_or_default()).await? {
+            copy_tags.push(
+                Tag::builder()
+                    .key("rds-snapshot-share:kms-alias")
+                    .value(alias)
+                    .build(),
+            );
+        }
+    }
+
+    let share_id = match copy_snapshot_id {
+        Some(copy_snapshot_id) => {
+            let copy = rds
+                .copy_snapshot(
+                    &args.db_type,
+                    snapshot_id,
+                    copy_snapshot_id,
+                    Some(kms_key_id.clone()),
+                    copy_tags,
+                )
+                .await?;
+
+            if args.no_wait_for_copy {
+                if text {
+                    println!("{} {}", copy.id, copy.arn);
+                }
+
+                ShareSummary {
+                    db_identifier: identifier,
+                    source_snapshot_id: source.id,
+                    snapshot_id: copy.id,
+                    kms_key_id,
+                    kms_key_description,
+                    account_ids: vec![],
+                    copied,
+                    status: copy.status,
+                    duration_ms: started.elapsed().as_millis(),
+                    org_policy_statement: None,
+                }
+                .print(&args.output);
+
+                return Ok(());
+            }
+
+            let notification = args
+                .snapshot_progress_notification_threshold
+                .map(|percent| ProgressNotification {
+                    sns: SNS::new(config),
+                    topic_arn: args.sns_topic_arn.clone().unwrap(),
+                    percent,
+                });
+
+            let copy = wait_for_snapshot(rds, &args.db_type, &copy.id, None, notification.as_ref())
+                .await?;
+
+            if copy.status == "failed" {
+                return Err(ShareError(format!("Copy {} failed", copy.id)));
+            }
+
+            copy.id
+        }
+        None => snapshot_id,
+    };
+
+    if !account_ids.is_empty() {
+        let shared_with = rds
+            .share_snapshot(&args.db_type, share_id.clone(), account_ids.clone())
+            .await?;
+
+        if text {
+            println!("Shared with {}", shared_with.join(","));
+        }
+
+        if args.snapshot_tag_accounts_count {
+            rds.tag_shared_accounts(&args.db_type, share_id.clone(), shared_with.len())
+                .await?;
+        }
+    }
+
+    if args.kms_key_policy_auto_update_for_org {
+        let org_id = Organizations::new(config)
+            .describe_organization_id()
+            .await?;
+
+        let updated = kms.allow_organization(&policy_key_arn, &org_id).await?;
+
+        if text {
+            match updated {
+                true => println!("Allowed organization {} to use {}", org_id, policy_key_arn),
+                false => println!("{} already allows organization {}", policy_key_arn, org_id),
+            }
+        }
+    }
+
+    if let Some((export_task_id, bucket, prefix, role_arn)) = export_destination {
+        let shared = rds
+            .describe_snapshot(&args.db_type, share_id.clone())
+            .await?;
+
+        let status = rds
+            .start_export_task(
+                export_task_id,
+                shared.arn,
+                bucket.clone(),
+                prefix,
+                role_arn,
+                kms_key_id.clone(),
+            )
+            .await?;
+
+        if text {
+            println!("Export of {} to {} is {}", share_id, bucket, status);
+        }
+    }
+
+    if let Some(path) = &args.generate_restore_guide {
+        let (shared, details) = join!(
+            rds.describe_snapshot(&args.db_type, share_id.clone()),
+            rds.describe_database_details(&args.db_type, identifier.clone()),
+        );
+
+        let shared = shared?;
+
+        let guide = RestoreGuide::new(
+            &args.db_type,
+            shared.arn,
+            shared.kms_key_id.unwrap_or_default(),
+            account_ids.clone(),
+            details?,
+            args.copy_storage_type.as_ref(),
+        );
+
+        // The share has already succeeded, so the summary and notifications still go out
+        if let Err(err) = guide.write(path) {
+            eprintln!(
+                "Warning: could not write the restore guide to {}: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+
+    let org_policy_statement = match args.include_snapshot_in_org_policy {
+        true => {
+            let shared = rds
+                .describe_snapshot(&args.db_type, share_id.clone())
+                .await?;
+
+            Some(org_policy_statement(&args.db_type, &shared.arn))
+        }
+        false => None,
+    };
+
+    // Other output modes carry the statement in the summary, keeping stdout a single document
+    if let (true, Some(statement)) = (text, &org_policy_statement) {
+        println!("{}", serde_json::to_string_pretty(statement).unwrap());
+    }
+
+    let summary = ShareSummary {
+        db_identifier: identifier,
+        source_snapshot_id: source.id,
+        snapshot_id: share_id,
+        kms_key_id,
+        kms_key_description,
+        account_ids,
+        copied,
+        status: "succeeded".to_string(),
+        duration_ms: started.elapsed().as_millis(),
+        org_policy_statement,
+    };
+
+    summary.print(&args.output);
+
+    // The share has already succeeded, so a failed notification only warns and the
+    // remaining notifiers still run
+    #[cfg(feature = "cloudwatch-metrics")]
+    if args.emit_metrics {
+        if let Err(err) = CloudWatch::new(config)
+            .put_share_metrics(&args.metrics_namespace, &summary)
+            .await
+        {
+            eprintln!("Warning: could not put CloudWatch metrics: {}", err);
+        }
+    }
+
+    #[cfg(feature = "slack")]
+    if let Some(webhook_url) = args.slack_webhook {
+        if let Err(err) = Slack::new(webhook_url).post_summary(&summary).await {
+            eprintln!("Warning: could not notify Slack: {}", err);
+        }
+    }
+
+    #[cfg(feature = "teams")]
+    if let Some(webhook_url) = args.teams_webhook {
+        if let Err(err) = Teams::new(webhook_url)
+            .post_summary(args.teams_title, &summary)
+            .await
+        {
+            eprintln!("Warning: could not notify Teams: {}", err);
+        }
+    }
+
+    if let Some(topic_arn) = &args.sns_topic_arn {
+        if let Err(err) = SNS::new(config)
+            .publish(
+                topic_arn,
+                "RDS snapshot share succeeded",
+                serde_json::to_string_pretty(&summary).unwrap(),
+            )
+            .await
+        {
+            eprintln!(
+                "Warning: could not publish to SNS topic {}: {}",
+                topic_arn, err
+            );
+        }
+    }
+
+    if args.eventbridge_notification {
+        match EventBridge::new(config)
+            .put_share_event(&args.eventbridge_bus_name, &summary)
+            .await
+        {
+            Ok(0) => (),
+            Ok(_) => eprintln!(
+                "EventBridge rejected the completion event on bus {}",
+                args.eventbridge_bus_name
+            ),
+            Err(err) => eprintln!(
+                "Warning: could not put the completion event on bus {}: {}",
+                args.eventbridge_bus_name, err
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn default_copy_snapshot_id_appends_copy_suffix() {
+        let generated = default_copy_snapshot_id(
+            "arn:aws:rds:us-east-1:123456789012:snapshot:db-1",
+            None,
+            RDS_MAX_IDENTIFIER_LENGTH,
+        );
+        let (name, copied) = generated.split_once("-copy-").unwrap();
+
+        assert_eq!(name, "db-1");
+        assert_eq!(copied.len(), 14);
+        assert!(copied.chars().all(|c| c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn default_copy_snapshot_id_includes_pitr_timestamp() {
+        let generated = default_copy_snapshot_id("db-1", Some(at(3, 4)), RDS_MAX_IDENTIFIER_LENGTH);
+
+        assert!(generated.starts_with("db-1-pitr-20240102030400-copy-"));
+    }
+
+    #[test]
+    fn default_copy_snapshot_id_truncates_long_names() {
+        let generated = default_copy_snapshot_id(&"a".repeat(300), None, RDS_MAX_IDENTIFIER_LENGTH);
+
+        assert_eq!(generated.len(), RDS_MAX_IDENTIFIER_LENGTH);
+        assert!(generated.starts_with(&"a".repeat(235)));
+    }
+
+    #[test]
+    fn default_copy_snapshot_id_truncates_to_max_length() {
+        let generated = default_copy_snapshot_id(&"a".repeat(100), None, 63);
+
+        assert_eq!(generated.len(), 63);
+        assert!(generated.starts_with(&format!("{}-copy-", "a".repeat(43))));
+    }
+
+    #[test]
+    fn default_copy_snapshot_id_trims_hyphen_at_truncation() {
+        let name = format!("{}-{}", "a".repeat(234), "b".repeat(30));
+        let generated = default_copy_snapshot_id(&name, None, RDS_MAX_IDENTIFIER_LENGTH);
+
+        assert!(generated.starts_with(&format!("{}-copy-", "a".repeat(234))));
+    }
+
+    #[test]
+    fn validate_snapshot_id_rejects_non_ascii() {
+        assert!(validate_snapshot_id(&"é".repeat(200)).is_err());
+        assert!(validate_snapshot_id("arn:aws:rds:us-east-1:123456789012:snapshot:db-1").is_ok());
+        assert!(validate_snapshot_id(&"a".repeat(256)).is_err());
+    }
+
+    #[test]
+    fn snapshot_arn_parses_cluster_and_instance_snapshots() {
+        let cluster: SnapshotArn = "arn:aws:rds:eu-west-1:123456789012:cluster-snapshot:c-1"
+            .parse()
+            .unwrap();
+
+        assert_eq!(cluster.region, "eu-west-1");
+        assert!(matches!(cluster.db_type, DatabaseType::Cluster));
+        assert_eq!(cluster.snapshot_id, "c-1");
+
+        let instance: SnapshotArn = "arn:aws:rds:us-east-1:123456789012:snapshot:rds:db-1"
+            .parse()
+            .unwrap();
+
+        assert!(matches!(instance.db_type, DatabaseType::Database));
+        assert_eq!(instance.snapshot_id, "rds:db-1");
+    }
+
+    #[test]
+    fn snapshot_arn_rejects_other_resources() {
+        assert!("arn:aws:rds:us-east-1:123456789012:db:db-1"
+            .parse::<SnapshotArn>()
+            .is_err());
+        assert!("arn:aws:s3:::bucket".parse::<SnapshotArn>().is_err());
+        assert!("db-1".parse::<SnapshotArn>().is_err());
+    }
+
+    #[test]
+    fn parse_s3_uri_splits_bucket_and_prefix() {
+        assert_eq!(
+            parse_s3_uri("s3://my-bucket/exports/db"),
+            Some(("my-bucket".to_string(), Some("exports/db".to_string())))
+        );
+        assert_eq!(
+            parse_s3_uri("s3://my-bucket/"),
+            Some(("my-bucket".to_string(), None))
+        );
+        assert_eq!(
+            parse_s3_uri("s3://my-bucket"),
+            Some(("my-bucket".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn parse_s3_uri_rejects_invalid_buckets() {
+        assert_eq!(parse_s3_uri("s3://My_Bucket"), None);
+        assert_eq!(parse_s3_uri("s3://ab"), None);
+        assert_eq!(parse_s3_uri("https://my-bucket"), None);
+    }
+
+    #[test]
+    fn parse_https_url_accepts_https_hosts() {
+        assert_eq!(
+            parse_https_url("https://vpce-1.rds.us-east-1.vpce.amazonaws.com/"),
+            Ok("https://vpce-1.rds.us-east-1.vpce.amazonaws.com".to_string())
+        );
+        assert_eq!(
+            parse_https_url("https://localhost:4566"),
+            Ok("https://localhost:4566".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_https_url_rejects_other_urls() {
+        assert!(parse_https_url("http://localhost:4566").is_err());
+        assert!(parse_https_url("https://").is_err());
+        assert!(parse_https_url("https://:443").is_err());
+        assert!(parse_https_url("https://local host").is_err());
+    }
+
+    #[test]
+    fn regional_endpoint_url_applies_to_the_configured_region() {
+        let config = SdkConfig::builder()
+            .region(Region::new("us-east-1"))
+            .build();
+        let url = Some("https://vpce-1.rds.us-east-1.vpce.amazonaws.com");
+
+        assert_eq!(regional_endpoint_url(&config, "us-east-1", url), url);
+        assert_eq!(regional_endpoint_url(&config, "eu-west-1", url), None);
+        assert_eq!(regional_endpoint_url(&config, "us-east-1", None), None);
+    }
+
+    #[test]
+    fn backup_window_active_within_a_day() {
+        assert!(backup_window_active("03:00-03:30", at(3, 10)));
+        assert!(!backup_window_active("03:00-03:30", at(3, 30)));
+        assert!(!backup_window_active("03:00-03:30", at(2, 59)));
+    }
+
+    #[test]
+    fn backup_window_active_wraps_past_midnight() {
+        assert!(backup_window_active("23:30-00:30", at(23, 45)));
+        assert!(backup_window_active("23:30-00:30", at(0, 15)));
+        assert!(!backup_window_active("23:30-00:30", at(0, 30)));
+        assert!(!backup_window_active("23:30-00:30", at(12, 0)));
+    }
+
+    #[test]
+    fn backup_window_active_ignores_malformed_windows() {
+        assert!(!backup_window_active("03:00", at(3, 0)));
+        assert!(!backup_window_active("aa:00-03:30", at(3, 0)));
+    }
 }